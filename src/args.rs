@@ -1,4 +1,6 @@
-use crate::ui::OverwriteMode;
+use unitypackage_extractor::{
+    backup_session_dir, CompressOptions, DiffFormat, ExtractOptions, ManifestFormat, MergePolicy, OverwriteMode, OverwriteModes, OverwritePolicy, PackageEdit,
+};
 use std::path::PathBuf;
 
 /// コマンドの種類
@@ -8,13 +10,49 @@ pub enum Command {
     Extract {
         input_file: PathBuf,
         output_dir: Option<PathBuf>,
-        overwrite_mode: OverwriteMode,
+        /// metaファイルとassetファイルそれぞれの上書きモード
+        overwrite_modes: OverwriteModes,
+        options: ExtractOptions,
+        /// 展開したエントリのマニフェストを標準出力に書く形式
+        format: Option<ManifestFormat>,
+        /// ディスクに書き込まずに処理内容だけを表示する
+        dry_run: bool,
     },
     /// 圧縮モード
     Compress {
         input_dir: PathBuf,
         output_file: PathBuf,
-        project_root: Option<PathBuf>,
+        options: CompressOptions,
+    },
+    /// 一覧表示モード
+    List {
+        input_file: PathBuf,
+        format: Option<ManifestFormat>,
+    },
+    /// 検証モード
+    Verify { input_file: PathBuf },
+    /// 結合モード
+    Merge {
+        input_files: Vec<PathBuf>,
+        output_file: PathBuf,
+        policy: MergePolicy,
+    },
+    /// 編集モード（remove/move/replace）
+    Edit {
+        input_file: PathBuf,
+        /// 省略時は入力ファイルを置き換える
+        output_file: Option<PathBuf>,
+        edit: PackageEdit,
+    },
+    /// アンインストールモード
+    Uninstall { input_file: PathBuf, project_dir: PathBuf },
+    /// 復元モード（`--backup-dir` で退避したファイルを戻す）
+    Restore { backup_dir: PathBuf, project_dir: PathBuf },
+    /// 差分表示モード
+    Diff {
+        old_file: PathBuf,
+        new_file: PathBuf,
+        format: DiffFormat,
     },
 }
 
@@ -29,6 +67,13 @@ impl Args {
         match &self.command {
             Command::Extract { input_file, .. } => input_file,
             Command::Compress { input_dir, .. } => input_dir,
+            Command::List { input_file, .. } => input_file,
+            Command::Verify { input_file } => input_file,
+            Command::Diff { new_file, .. } => new_file,
+            Command::Merge { output_file, .. } => output_file,
+            Command::Edit { input_file, .. } => input_file,
+            Command::Uninstall { input_file, .. } => input_file,
+            Command::Restore { backup_dir, .. } => backup_dir,
         }
     }
 
@@ -36,16 +81,30 @@ impl Args {
     pub fn output_dir(&self) -> Option<&PathBuf> {
         match &self.command {
             Command::Extract { output_dir, .. } => output_dir.as_ref(),
-            Command::Compress { .. } => None,
+            Command::Compress { .. }
+            | Command::List { .. }
+            | Command::Verify { .. }
+            | Command::Diff { .. }
+            | Command::Merge { .. }
+            | Command::Edit { .. }
+            | Command::Uninstall { .. }
+            | Command::Restore { .. } => None,
         }
     }
 
     #[allow(dead_code)]
     #[cfg_attr(feature = "gui", allow(dead_code))]
-    pub fn overwrite_mode(&self) -> OverwriteMode {
+    pub fn overwrite_modes(&self) -> OverwriteModes {
         match &self.command {
-            Command::Extract { overwrite_mode, .. } => *overwrite_mode,
-            Command::Compress { .. } => OverwriteMode::Rename,
+            Command::Extract { overwrite_modes, .. } => *overwrite_modes,
+            Command::Compress { .. }
+            | Command::List { .. }
+            | Command::Verify { .. }
+            | Command::Diff { .. }
+            | Command::Merge { .. }
+            | Command::Edit { .. }
+            | Command::Uninstall { .. }
+            | Command::Restore { .. } => OverwriteMode::Rename.into(),
         }
     }
 }
@@ -66,7 +125,8 @@ impl Args {
 
         if args.len() > 1 && !args[1].starts_with("--") {
             match args[1].as_str() {
-                "extract" | "compress" => {
+                "extract" | "compress" | "list" | "verify" | "diff" | "merge" | "remove" | "move" | "replace"
+                | "uninstall" | "restore" => {
                     subcommand = args[1].clone();
                     start_idx = 2;
                 }
@@ -85,6 +145,13 @@ impl Args {
         match subcommand.as_str() {
             "extract" => Self::parse_extract(&args, start_idx),
             "compress" => Self::parse_compress(&args, start_idx),
+            "list" => Self::parse_list(&args, start_idx),
+            "verify" => Self::parse_verify(&args, start_idx),
+            "diff" => Self::parse_diff(&args, start_idx),
+            "merge" => Self::parse_merge(&args, start_idx),
+            "remove" | "move" | "replace" => Self::parse_edit(&args, start_idx, &subcommand),
+            "uninstall" => Self::parse_uninstall(&args, start_idx),
+            "restore" => Self::parse_restore(&args, start_idx),
             _ => Err(format!("Unknown subcommand: {}\n\n{}", subcommand, Self::usage(&args[0]))),
         }
     }
@@ -92,6 +159,10 @@ impl Args {
     fn parse_extract(args: &[String], start_idx: usize) -> Result<Self, String> {
         let mut input_file: Option<PathBuf> = None;
         let mut output_dir: Option<PathBuf> = None;
+        // デフォルト値はCLI版・GUI版とも共通（`ExtractOptions::default`）
+        let mut options = ExtractOptions::default();
+        let mut format: Option<ManifestFormat> = None;
+        let mut dry_run = false;
 
         // デフォルト値: GUI版はAsk、CLI版はRename
         #[cfg(feature = "gui")]
@@ -100,19 +171,36 @@ impl Args {
         #[cfg(not(feature = "gui"))]
         let mut overwrite_mode = OverwriteMode::Rename;
 
+        // 個別に指定されていない場合は --overwrite-mode の値を使用する
+        let mut meta_overwrite_mode: Option<OverwriteMode> = None;
+        let mut asset_overwrite_mode: Option<OverwriteMode> = None;
+        // 実際のバックアップ先は、この下に作成するタイムスタンプ付きのディレクトリ
+        let mut backup_root: Option<PathBuf> = None;
+
         let mut i = start_idx;
         while i < args.len() {
             let arg = &args[i];
 
             if arg.starts_with("--overwrite-mode=") {
-                let mode = arg.strip_prefix("--overwrite-mode=").unwrap();
-                overwrite_mode = match mode {
-                    "overwrite" => OverwriteMode::Overwrite,
-                    "skip" => OverwriteMode::Skip,
-                    "rename" => OverwriteMode::Rename,
-                    "ask" => OverwriteMode::Ask,
-                    _ => return Err(format!("Invalid overwrite mode: {}. Use: overwrite, skip, rename, or ask", mode)),
-                };
+                overwrite_mode = Self::parse_overwrite_mode(arg.strip_prefix("--overwrite-mode=").unwrap())?;
+            } else if arg.starts_with("--meta-overwrite-mode=") {
+                let mode = arg.strip_prefix("--meta-overwrite-mode=").unwrap();
+                meta_overwrite_mode = Some(Self::parse_overwrite_mode(mode)?);
+            } else if arg == "--meta-overwrite-mode" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--meta-overwrite-mode requires a value".to_string());
+                }
+                meta_overwrite_mode = Some(Self::parse_overwrite_mode(&args[i])?);
+            } else if arg.starts_with("--asset-overwrite-mode=") {
+                let mode = arg.strip_prefix("--asset-overwrite-mode=").unwrap();
+                asset_overwrite_mode = Some(Self::parse_overwrite_mode(mode)?);
+            } else if arg == "--asset-overwrite-mode" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--asset-overwrite-mode requires a value".to_string());
+                }
+                asset_overwrite_mode = Some(Self::parse_overwrite_mode(&args[i])?);
             } else if arg.starts_with("--output-dir=") {
                 let dir = arg.strip_prefix("--output-dir=").unwrap();
                 output_dir = Some(PathBuf::from(dir));
@@ -122,6 +210,98 @@ impl Args {
                     return Err("--output-dir requires a value".to_string());
                 }
                 output_dir = Some(PathBuf::from(&args[i]));
+            } else if arg.starts_with("--include=") {
+                options.filter.include.push(arg.strip_prefix("--include=").unwrap().to_string());
+            } else if arg == "--include" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--include requires a value".to_string());
+                }
+                options.filter.include.push(args[i].clone());
+            } else if arg.starts_with("--exclude=") {
+                options.filter.exclude.push(arg.strip_prefix("--exclude=").unwrap().to_string());
+            } else if arg == "--exclude" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--exclude requires a value".to_string());
+                }
+                options.filter.exclude.push(args[i].clone());
+            } else if arg.starts_with("--format=") {
+                format = Some(Self::parse_format(arg.strip_prefix("--format=").unwrap())?);
+            } else if arg == "--format" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--format requires a value".to_string());
+                }
+                format = Some(Self::parse_format(&args[i])?);
+            } else if arg.starts_with("--export-previews=") {
+                let dir = arg.strip_prefix("--export-previews=").unwrap();
+                options.export_previews = Some(PathBuf::from(dir));
+            } else if arg == "--export-previews" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--export-previews requires a value".to_string());
+                }
+                options.export_previews = Some(PathBuf::from(&args[i]));
+            } else if arg.starts_with("--prefix=") {
+                options.relocation.prefix = Some(arg.strip_prefix("--prefix=").unwrap().to_string());
+            } else if arg == "--prefix" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--prefix requires a value".to_string());
+                }
+                options.relocation.prefix = Some(args[i].clone());
+            } else if arg.starts_with("--strip-prefix=") {
+                options.relocation.strip_prefix = Some(arg.strip_prefix("--strip-prefix=").unwrap().to_string());
+            } else if arg == "--strip-prefix" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--strip-prefix requires a value".to_string());
+                }
+                options.relocation.strip_prefix = Some(args[i].clone());
+            } else if arg.starts_with("--strip-components=") {
+                options.relocation.strip_components =
+                    Self::parse_strip_components(arg.strip_prefix("--strip-components=").unwrap())?;
+            } else if arg == "--strip-components" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--strip-components requires a value".to_string());
+                }
+                options.relocation.strip_components = Self::parse_strip_components(&args[i])?;
+            } else if arg.starts_with("--policy-file=") {
+                options.overwrite_policy = Self::load_policy(arg.strip_prefix("--policy-file=").unwrap())?;
+            } else if arg == "--policy-file" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--policy-file requires a value".to_string());
+                }
+                options.overwrite_policy = Self::load_policy(&args[i])?;
+            } else if arg.starts_with("--backup-dir=") {
+                backup_root = Some(PathBuf::from(arg.strip_prefix("--backup-dir=").unwrap()));
+            } else if arg == "--backup-dir" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--backup-dir requires a value".to_string());
+                }
+                backup_root = Some(PathBuf::from(&args[i]));
+            } else if arg == "--skip-identical" {
+                options.skip_identical = true;
+            } else if arg == "--no-skip-identical" {
+                options.skip_identical = false;
+            } else if arg == "--fail-on-guid-conflict" {
+                options.fail_on_guid_conflict = true;
+            } else if arg == "--remap-guids" {
+                options.remap_guids = true;
+            } else if arg == "--upgrade" {
+                options.upgrade = true;
+            } else if arg == "--rewrite-renamed-references" {
+                options.rewrite_renamed_references = true;
+            } else if arg == "--dry-run" {
+                dry_run = true;
+            } else if arg == "--direct" {
+                options.direct = true;
+            } else if arg == "--allow-unsafe-paths" {
+                options.allow_unsafe_paths = true;
             } else if arg == "--help" || arg == "-h" {
                 println!("{}", Self::usage(&args[0]));
                 std::process::exit(0);
@@ -154,20 +334,38 @@ impl Args {
             ));
         }
 
+        let overwrite_modes = OverwriteModes {
+            meta: meta_overwrite_mode.unwrap_or(overwrite_mode),
+            asset: asset_overwrite_mode.unwrap_or(overwrite_mode),
+        };
+
         // CLI版では Ask モードを明示的に拒否
         #[cfg(not(feature = "gui"))]
-        if overwrite_mode == OverwriteMode::Ask {
+        if overwrite_modes.meta == OverwriteMode::Ask || overwrite_modes.asset == OverwriteMode::Ask {
             return Err(format!(
                 "Error: --overwrite-mode=ask is not supported in CLI mode.\nPlease use: overwrite, skip, or rename.\n\n{}",
                 Self::usage(&args[0])
             ));
         }
 
+        if options.remap_guids && options.upgrade {
+            return Err("--remap-guids cannot be combined with --upgrade".to_string());
+        }
+
+        if dry_run && format.is_some() {
+            return Err("--dry-run cannot be combined with --format".to_string());
+        }
+
+        options.backup_dir = backup_root.map(|root| backup_session_dir(&root));
+
         Ok(Args {
             command: Command::Extract {
                 input_file,
                 output_dir,
-                overwrite_mode,
+                overwrite_modes,
+                options,
+                format,
+                dry_run,
             },
         })
     }
@@ -230,11 +428,400 @@ impl Args {
             command: Command::Compress {
                 input_dir,
                 output_file,
-                project_root,
+                options: CompressOptions { project_root },
             },
         })
     }
 
+    fn parse_list(args: &[String], start_idx: usize) -> Result<Self, String> {
+        let mut input_file: Option<PathBuf> = None;
+        let mut format: Option<ManifestFormat> = None;
+
+        let mut i = start_idx;
+        while i < args.len() {
+            let arg = &args[i];
+
+            if arg.starts_with("--format=") {
+                format = Some(Self::parse_format(arg.strip_prefix("--format=").unwrap())?);
+            } else if arg == "--format" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--format requires a value".to_string());
+                }
+                format = Some(Self::parse_format(&args[i])?);
+            } else if arg == "--help" || arg == "-h" {
+                println!("{}", Self::usage(&args[0]));
+                std::process::exit(0);
+            } else if !arg.starts_with("--") {
+                // 位置引数
+                if input_file.is_none() {
+                    input_file = Some(PathBuf::from(arg));
+                } else {
+                    return Err(format!("Unknown argument: {}", arg));
+                }
+            } else {
+                return Err(format!("Unknown option: {}", arg));
+            }
+
+            i += 1;
+        }
+
+        let input_file = input_file.ok_or_else(|| {
+            format!("Input file is required for list command\n\n{}", Self::usage(&args[0]))
+        })?;
+
+        Ok(Args {
+            command: Command::List { input_file, format },
+        })
+    }
+
+    fn parse_uninstall(args: &[String], start_idx: usize) -> Result<Self, String> {
+        let mut input_file: Option<PathBuf> = None;
+        let mut project_dir: Option<PathBuf> = None;
+
+        let mut i = start_idx;
+        while i < args.len() {
+            let arg = &args[i];
+
+            if arg.starts_with("--project=") {
+                project_dir = Some(PathBuf::from(arg.strip_prefix("--project=").unwrap()));
+            } else if arg == "--project" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--project requires a value".to_string());
+                }
+                project_dir = Some(PathBuf::from(&args[i]));
+            } else if arg == "--help" || arg == "-h" {
+                println!("{}", Self::usage(&args[0]));
+                std::process::exit(0);
+            } else if !arg.starts_with("--") {
+                // 位置引数
+                if input_file.is_none() {
+                    input_file = Some(PathBuf::from(arg));
+                } else {
+                    return Err(format!("Unknown argument: {}", arg));
+                }
+            } else {
+                return Err(format!("Unknown option: {}", arg));
+            }
+
+            i += 1;
+        }
+
+        let input_file = input_file.ok_or_else(|| {
+            format!("Input file is required for uninstall command\n\n{}", Self::usage(&args[0]))
+        })?;
+        let project_dir = project_dir.ok_or_else(|| {
+            format!("--project is required for uninstall command\n\n{}", Self::usage(&args[0]))
+        })?;
+
+        Ok(Args {
+            command: Command::Uninstall { input_file, project_dir },
+        })
+    }
+
+    fn parse_restore(args: &[String], start_idx: usize) -> Result<Self, String> {
+        let mut backup_dir: Option<PathBuf> = None;
+        let mut project_dir: Option<PathBuf> = None;
+
+        let mut i = start_idx;
+        while i < args.len() {
+            let arg = &args[i];
+
+            if arg.starts_with("--project=") {
+                project_dir = Some(PathBuf::from(arg.strip_prefix("--project=").unwrap()));
+            } else if arg == "--project" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--project requires a value".to_string());
+                }
+                project_dir = Some(PathBuf::from(&args[i]));
+            } else if arg == "--help" || arg == "-h" {
+                println!("{}", Self::usage(&args[0]));
+                std::process::exit(0);
+            } else if !arg.starts_with("--") {
+                // 位置引数
+                if backup_dir.is_none() {
+                    backup_dir = Some(PathBuf::from(arg));
+                } else {
+                    return Err(format!("Unknown argument: {}", arg));
+                }
+            } else {
+                return Err(format!("Unknown option: {}", arg));
+            }
+
+            i += 1;
+        }
+
+        let backup_dir = backup_dir.ok_or_else(|| {
+            format!("Backup directory is required for restore command\n\n{}", Self::usage(&args[0]))
+        })?;
+        let project_dir = project_dir.ok_or_else(|| {
+            format!("--project is required for restore command\n\n{}", Self::usage(&args[0]))
+        })?;
+
+        Ok(Args {
+            command: Command::Restore { backup_dir, project_dir },
+        })
+    }
+
+    fn parse_verify(args: &[String], start_idx: usize) -> Result<Self, String> {
+        let mut input_file: Option<PathBuf> = None;
+
+        for arg in &args[start_idx..] {
+            if arg == "--help" || arg == "-h" {
+                println!("{}", Self::usage(&args[0]));
+                std::process::exit(0);
+            } else if !arg.starts_with("--") {
+                // 位置引数
+                if input_file.is_none() {
+                    input_file = Some(PathBuf::from(arg));
+                } else {
+                    return Err(format!("Unknown argument: {}", arg));
+                }
+            } else {
+                return Err(format!("Unknown option: {}", arg));
+            }
+        }
+
+        let input_file = input_file.ok_or_else(|| {
+            format!("Input file is required for verify command\n\n{}", Self::usage(&args[0]))
+        })?;
+
+        Ok(Args {
+            command: Command::Verify { input_file },
+        })
+    }
+
+    fn parse_diff(args: &[String], start_idx: usize) -> Result<Self, String> {
+        let mut old_file: Option<PathBuf> = None;
+        let mut new_file: Option<PathBuf> = None;
+        let mut format = DiffFormat::default();
+
+        let mut i = start_idx;
+        while i < args.len() {
+            let arg = &args[i];
+
+            if arg.starts_with("--format=") {
+                format = Self::parse_diff_format(arg.strip_prefix("--format=").unwrap())?;
+            } else if arg == "--format" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--format requires a value".to_string());
+                }
+                format = Self::parse_diff_format(&args[i])?;
+            } else if arg == "--help" || arg == "-h" {
+                println!("{}", Self::usage(&args[0]));
+                std::process::exit(0);
+            } else if !arg.starts_with("--") {
+                // 位置引数
+                if old_file.is_none() {
+                    old_file = Some(PathBuf::from(arg));
+                } else if new_file.is_none() {
+                    new_file = Some(PathBuf::from(arg));
+                } else {
+                    return Err(format!("Unknown argument: {}", arg));
+                }
+            } else {
+                return Err(format!("Unknown option: {}", arg));
+            }
+
+            i += 1;
+        }
+
+        let (Some(old_file), Some(new_file)) = (old_file, new_file) else {
+            return Err(format!(
+                "Two input files are required for diff command\n\n{}",
+                Self::usage(&args[0])
+            ));
+        };
+
+        Ok(Args {
+            command: Command::Diff { old_file, new_file, format },
+        })
+    }
+
+    fn parse_merge(args: &[String], start_idx: usize) -> Result<Self, String> {
+        let mut input_files: Vec<PathBuf> = Vec::new();
+        let mut output_file: Option<PathBuf> = None;
+        let mut policy = MergePolicy::default();
+
+        let mut i = start_idx;
+        while i < args.len() {
+            let arg = &args[i];
+
+            if arg.starts_with("--output=") {
+                output_file = Some(PathBuf::from(arg.strip_prefix("--output=").unwrap()));
+            } else if arg == "--output" || arg == "-o" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--output requires a value".to_string());
+                }
+                output_file = Some(PathBuf::from(&args[i]));
+            } else if arg.starts_with("--on-conflict=") {
+                policy = Self::parse_merge_policy(arg.strip_prefix("--on-conflict=").unwrap())?;
+            } else if arg == "--on-conflict" {
+                i += 1;
+                if i >= args.len() {
+                    return Err("--on-conflict requires a value".to_string());
+                }
+                policy = Self::parse_merge_policy(&args[i])?;
+            } else if arg == "--help" || arg == "-h" {
+                println!("{}", Self::usage(&args[0]));
+                std::process::exit(0);
+            } else if !arg.starts_with("--") {
+                // 位置引数（結合するパッケージ）
+                input_files.push(PathBuf::from(arg));
+            } else {
+                return Err(format!("Unknown option: {}", arg));
+            }
+
+            i += 1;
+        }
+
+        if input_files.len() < 2 {
+            return Err(format!(
+                "At least two input files are required for merge command\n\n{}",
+                Self::usage(&args[0])
+            ));
+        }
+
+        let output_file = output_file.ok_or_else(|| {
+            format!("Output file is required for merge command\n\n{}", Self::usage(&args[0]))
+        })?;
+
+        Ok(Args {
+            command: Command::Merge {
+                input_files,
+                output_file,
+                policy,
+            },
+        })
+    }
+
+    fn parse_edit(args: &[String], start_idx: usize, subcommand: &str) -> Result<Self, String> {
+        let mut input_file: Option<PathBuf> = None;
+        let mut output_file: Option<PathBuf> = None;
+        let mut paths: Vec<String> = Vec::new();
+        let mut from: Option<String> = None;
+        let mut to: Option<String> = None;
+        let mut guid: Option<String> = None;
+        let mut file: Option<PathBuf> = None;
+
+        let mut i = start_idx;
+        while i < args.len() {
+            let arg = &args[i];
+
+            // --name=value と --name value の両方を受け付ける
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let takes_value = matches!(
+                (subcommand, name),
+                (_, "--output" | "-o") | ("remove", "--path") | ("move", "--from" | "--to") | ("replace", "--guid" | "--file")
+            );
+
+            if takes_value {
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => {
+                        i += 1;
+                        if i >= args.len() {
+                            return Err(format!("{} requires a value", name));
+                        }
+                        args[i].clone()
+                    }
+                };
+                match name {
+                    "--output" | "-o" => output_file = Some(PathBuf::from(value)),
+                    "--path" => paths.push(value),
+                    "--from" => from = Some(value),
+                    "--to" => to = Some(value),
+                    "--guid" => guid = Some(value),
+                    _ => file = Some(PathBuf::from(value)),
+                }
+            } else if arg == "--help" || arg == "-h" {
+                println!("{}", Self::usage(&args[0]));
+                std::process::exit(0);
+            } else if !arg.starts_with("--") {
+                // 位置引数
+                if input_file.is_none() {
+                    input_file = Some(PathBuf::from(arg));
+                } else {
+                    return Err(format!("Unknown argument: {}", arg));
+                }
+            } else {
+                return Err(format!("Unknown option: {}", arg));
+            }
+
+            i += 1;
+        }
+
+        let input_file = input_file.ok_or_else(|| {
+            format!("Input file is required for {} command\n\n{}", subcommand, Self::usage(&args[0]))
+        })?;
+
+        let edit = match subcommand {
+            "remove" => {
+                if paths.is_empty() {
+                    return Err("remove requires at least one --path".to_string());
+                }
+                PackageEdit::Remove { patterns: paths }
+            }
+            "move" => match (from, to) {
+                (Some(from), Some(to)) => PackageEdit::Move { from, to },
+                _ => return Err("move requires --from and --to".to_string()),
+            },
+            _ => match (guid, file) {
+                (Some(guid), Some(file)) => PackageEdit::Replace { guid, file },
+                _ => return Err("replace requires --guid and --file".to_string()),
+            },
+        };
+
+        Ok(Args {
+            command: Command::Edit {
+                input_file,
+                output_file,
+                edit,
+            },
+        })
+    }
+
+    fn parse_merge_policy(value: &str) -> Result<MergePolicy, String> {
+        MergePolicy::parse(value)
+            .ok_or_else(|| format!("Invalid conflict policy: {}. Use: error, first, or last", value))
+    }
+
+    fn parse_diff_format(value: &str) -> Result<DiffFormat, String> {
+        DiffFormat::parse(value).ok_or_else(|| format!("Invalid format: {}. Use: text or json", value))
+    }
+
+    fn parse_format(value: &str) -> Result<ManifestFormat, String> {
+        ManifestFormat::parse(value)
+            .ok_or_else(|| format!("Invalid format: {}. Use: json, csv, or tree", value))
+    }
+
+    fn parse_overwrite_mode(mode: &str) -> Result<OverwriteMode, String> {
+        match mode {
+            "overwrite" => Ok(OverwriteMode::Overwrite),
+            "skip" => Ok(OverwriteMode::Skip),
+            "rename" => Ok(OverwriteMode::Rename),
+            "ask" => Ok(OverwriteMode::Ask),
+            _ => Err(format!("Invalid overwrite mode: {}. Use: overwrite, skip, rename, or ask", mode)),
+        }
+    }
+
+    fn load_policy(path: &str) -> Result<OverwritePolicy, String> {
+        OverwritePolicy::load(&PathBuf::from(path)).map_err(|e| e.to_string())
+    }
+
+    fn parse_strip_components(value: &str) -> Result<usize, String> {
+        value
+            .parse()
+            .map_err(|_| format!("Invalid value for --strip-components: {}. Use a non-negative integer", value))
+    }
+
     fn usage(program: &str) -> String {
         format!(
             "Usage: {} [COMMAND] [OPTIONS]
@@ -242,6 +829,15 @@ impl Args {
 Commands:
   extract                 Extract .unitypackage file (default)
   compress                Compress directory to .unitypackage file
+  list                    List entries in .unitypackage file without extracting
+  verify                  Check the structure of a .unitypackage file
+  diff                    Compare two .unitypackage files
+  merge                   Combine several .unitypackage files into one
+  remove                  Remove entries from a .unitypackage file
+  move                    Change the pathname of entries in a .unitypackage file
+  replace                 Replace the asset of an entry in a .unitypackage file
+  uninstall               Remove the assets a .unitypackage file installed into a project
+  restore                 Put files saved by --backup-dir back into a project
 
 EXTRACT MODE:
   Usage: {} [extract] <input.unitypackage> [OPTIONS]
@@ -253,12 +849,79 @@ EXTRACT MODE:
     --output-dir <dir>      Output directory{}
     --overwrite-mode <mode> Overwrite mode: overwrite, skip, rename, ask
                             Default: {}
+    --meta-overwrite-mode <mode>
+                            Overwrite mode for existing .meta files only
+                            (e.g. skip to keep tuned import settings)
+    --asset-overwrite-mode <mode>
+                            Overwrite mode for existing asset files only
+                            Both default to --overwrite-mode. When a .meta is
+                            renamed, its asset is created under the new name too
+    --policy-file <file>    Per-path overwrite modes, one `<glob> <mode>` rule per line
+                            (mode: overwrite, skip, rename; # starts a comment).
+                            The first matching rule applies to both the asset and
+                            its .meta; unmatched paths use the modes above
+    --backup-dir <dir>      Before overwriting an existing asset or .meta, move it to
+                            <dir>/<timestamp>/<same relative path>. Use the restore
+                            command to put it back
+    --include <glob>        Extract only entries whose pathname matches (repeatable)
+    --exclude <glob>        Skip entries whose pathname matches (repeatable)
+                            Globs: * (within a folder), ** (any folders), ? (one char)
+    --direct                Write assets straight to the output directory
+                            without the temporary staging directory. Reads the
+                            package twice: once to check pathnames and GUID
+                            conflicts before writing, once to extract
+    --allow-unsafe-paths    Allow pathnames that are absolute or contain '..'
+                            (may write outside the output directory)
+    --export-previews <dir> Write preview.png thumbnails to <dir>/<pathname>.png
+    --strip-prefix <dir>    Extract only entries under <dir> and remove it from
+                            their pathnames
+    --strip-components <n>  Remove the first <n> folders from every pathname
+                            (entries with no more than <n> components are skipped)
+    --prefix <dir>          Prepend <dir> to every pathname
+                            Applied in order: --strip-prefix, --strip-components,
+                            --prefix. --include/--exclude match the original pathname
+    --skip-identical        Leave existing files and metas whose content is identical
+                            (same size and SHA-256) as they are, without prompting
+                            or renaming; only real differences follow the overwrite
+                            mode. Default: off
+    --no-skip-identical     Apply the overwrite mode to identical files too
+    --format <format>       Print a manifest of the extracted entries to stdout
+                            instead of progress: json, csv, tree
+    --dry-run               Report which files and metas would be created,
+                            overwritten, skipped or renamed without writing anything
+    --remap-guids           Assign every extracted entry a new GUID and rewrite
+                            `guid:` references in text-serialized assets (.prefab,
+                            .unity, .mat, .asset, .controller, .anim) to match, so two
+                            variants of a package can be imported side by side
+    --upgrade               If an asset with the same GUID exists at a different path,
+                            move it (with its .meta) to the package's pathname before
+                            extracting, like Unity's own import, then apply the
+                            overwrite mode
+    --rewrite-renamed-references
+                            A renamed copy gets a new GUID when an existing .meta
+                            stays at the original name. With this option, references
+                            between renamed copies are rewritten to the new GUIDs so
+                            the copied set stays self-consistent. Only text-serialized
+                            copies (files starting with %YAML) are rewritten; binary
+                            assets keep their original references
+    --fail-on-guid-conflict Abort when a GUID already exists at another path in the
+                            output directory, or a path exists with another GUID
+                            (by default these are only reported as warnings)
     -h, --help              Show this help message
 
   Examples:
     # CLI mode (GUI feature disabled)
     {} input.unitypackage --output-dir ./output --overwrite-mode=rename
 
+    # Check conflicts with an existing project before extracting
+    {} input.unitypackage --output-dir ./MyProject --overwrite-mode=skip --dry-run
+
+    # Extract only materials, skipping Editor folders
+    {} input.unitypackage --output-dir ./output --include 'Assets/Foo/Materials/**' --exclude '**/Editor'
+
+    # Install a package's Assets/ contents under Assets/ThirdParty
+    {program} input.unitypackage --output-dir ./MyProject --strip-prefix Assets --prefix Assets/ThirdParty
+
     # GUI mode (GUI feature enabled)
     {} input.unitypackage
     {} input.unitypackage --output-dir ./output
@@ -283,6 +946,115 @@ COMPRESS MODE:
 
     # プロジェクトルートを指定（Assets/MyPackageの内容をMyPackage/として圧縮）
     {} compress ./MyUnityProject/Assets/MyPackage output.unitypackage --project-root ./MyUnityProject/Assets
+
+LIST MODE:
+  Usage: {} list <input.unitypackage> [OPTIONS]
+
+  Arguments:
+    <input.unitypackage>    Input .unitypackage file
+
+  Options:
+    --format <format>       Machine-readable output: json, csv, tree
+                            (guid, pathname, kind, size, importer, sha256)
+
+  Prints GUID, pathname, asset size, type and whether a preview exists
+  for every entry without writing to disk.
+
+  Examples:
+    {} list input.unitypackage
+    {} list input.unitypackage --format json > manifest.json
+
+VERIFY MODE:
+  Usage: {program} verify <input.unitypackage>
+
+  Checks that every GUID folder has a pathname and an asset.meta whose guid
+  matches the folder name, that GUIDs are 32 lowercase hex characters, that
+  no two entries share a pathname, and that folders have no asset while other
+  entries do. Exits with a non-zero status and lists every issue found.
+
+  Examples:
+    {program} verify input.unitypackage
+
+DIFF MODE:
+  Usage: {program} diff <old.unitypackage> <new.unitypackage> [OPTIONS]
+
+  Options:
+    --format <format>       Output format: text (default), json
+
+  Matches entries by GUID and reports entries that were added, removed,
+  moved (same GUID, new pathname), content-changed (asset SHA-256 differs)
+  and meta-changed.
+
+  Examples:
+    {program} diff base-1.0.unitypackage base-1.1.unitypackage
+    {program} diff base-1.0.unitypackage base-1.1.unitypackage --format json
+
+MERGE MODE:
+  Usage: {program} merge <a.unitypackage> <b.unitypackage>... --output <out.unitypackage> [OPTIONS]
+
+  Options:
+    --output, -o <file>     Output .unitypackage file
+    --on-conflict <policy>  What to do when two packages contain the same GUID with
+                            different contents, or the same pathname with different
+                            GUIDs: error (default), first, last
+                            first keeps the entry from the earlier package, last
+                            replaces it with the entry from the later package
+
+  Entries are copied as-is from each package without extracting, so entries
+  without metas and preview images are kept. Identical entries are merged.
+
+  Examples:
+    {program} merge base.unitypackage addon.unitypackage -o combined.unitypackage
+    {program} merge base.unitypackage fix.unitypackage -o out.unitypackage --on-conflict last
+
+EDIT MODE:
+  Usage: {program} remove <input.unitypackage> --path <glob>... [--output <file>]
+  Usage: {program} move <input.unitypackage> --from <pathname> --to <pathname> [--output <file>]
+  Usage: {program} replace <input.unitypackage> --guid <guid> --file <file> [--output <file>]
+
+  Options:
+    --path <glob>           Remove entries whose pathname matches (repeatable);
+                            matching a folder also removes its contents
+    --from, --to <pathname> Move an entry and everything under it to a new pathname
+    --guid, --file          Replace the asset of the entry with the contents of <file>
+    --output, -o <file>     Write the edited package to <file> instead of
+                            replacing the input file
+
+  Examples:
+    {program} remove input.unitypackage --path 'Assets/Foo/Demo/**' --path '**/*.unity'
+    {program} move input.unitypackage --from Assets/Foo --to Assets/ThirdParty/Foo
+    {program} replace input.unitypackage --guid 0123456789abcdef0123456789abcdef --file new.png
+
+UNINSTALL MODE:
+  Usage: {program} uninstall <input.unitypackage> --project <dir>
+
+  Options:
+    --project <dir>         Unity project directory the package was extracted into
+
+  Assets are found by the GUID in their .meta, so assets moved after import are
+  also found. An asset (with its .meta) is removed only if its content is unchanged
+  from the package; modified assets are kept and reported. Folders are removed only
+  when they become empty.
+
+  Examples:
+    {program} uninstall input.unitypackage --project ./MyProject
+
+RESTORE MODE:
+  Usage: {program} restore <backup-dir> --project <dir>
+
+  Arguments:
+    <backup-dir>            A timestamped directory created by --backup-dir
+                            (e.g. ./backups/20240102-030405)
+
+  Options:
+    --project <dir>         Directory the files were extracted into
+
+  Moves every file in <backup-dir> back to the same relative path in the project,
+  replacing the current file, then removes the emptied backup directory.
+
+  Examples:
+    {program} input.unitypackage --output-dir ./MyProject --overwrite-mode=overwrite --backup-dir ./backups
+    {program} restore ./backups/20240102-030405 --project ./MyProject
 ",
             program,
             program,
@@ -302,6 +1074,11 @@ COMPRESS MODE:
             program,
             program,
             program,
+            program,
+            program,
+            program,
+            program,
+            program,
             program
         )
     }
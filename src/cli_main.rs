@@ -1,101 +1,326 @@
 use crate::args::{Args, Command};
-use crate::core::{extract_objects, rebuild_objects, compress_directory};
-use crate::ui::cli::CliProgressHandler;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::error::AppError;
+use unitypackage_extractor::{
+    compress_directory, edit_package, merge_packages, render_diff, render_manifest, restore_backup, CliProgressHandler,
+    CompressOptions, DiffFormat, Error, ExtractOptions, ManifestFormat, MergePolicy, OverwriteMode,
+    OverwriteModes, PackageEdit, PackageReader, PlannedAction, Result,
+};
+use std::path::{Path, PathBuf};
 
-const TMP_OUTPUT_DIR: &str = ".jp.ootr.unitypackage-extractor";
-
-pub fn run() -> Result<(), String> {
-    let args = Args::parse()?;
+pub fn run() -> std::result::Result<(), AppError> {
+    let args = Args::parse().map_err(AppError::InvalidArgument)?;
 
     match &args.command {
-        Command::Extract { input_file, output_dir, overwrite_mode } => {
-            run_extract(input_file, output_dir.as_ref(), *overwrite_mode)
+        Command::Extract {
+            input_file,
+            output_dir,
+            overwrite_modes,
+            options,
+            format,
+            dry_run,
+        } => {
+            let output_dir = output_dir
+                .as_deref()
+                .ok_or_else(|| AppError::InvalidArgument("--output-dir is required in CLI mode".to_string()))?;
+            if *dry_run {
+                run_dry_run(input_file, output_dir, *overwrite_modes, options)?
+            } else {
+                run_extract(input_file, output_dir, *overwrite_modes, options, *format)?
+            }
         }
-        Command::Compress { input_dir, output_file, project_root } => {
-            run_compress(input_dir, output_file, project_root.as_ref())
+        Command::Compress { input_dir, output_file, options } => {
+            run_compress(input_dir, output_file, options)?
         }
+        Command::List { input_file, format } => run_list(input_file, *format)?,
+        Command::Verify { input_file } => run_verify(input_file)?,
+        Command::Diff { old_file, new_file, format } => run_diff(old_file, new_file, *format)?,
+        Command::Merge {
+            input_files,
+            output_file,
+            policy,
+        } => run_merge(input_files, output_file, *policy)?,
+        Command::Edit {
+            input_file,
+            output_file,
+            edit,
+        } => run_edit(input_file, output_file.as_deref(), edit)?,
+        Command::Uninstall { input_file, project_dir } => run_uninstall(input_file, project_dir)?,
+        Command::Restore { backup_dir, project_dir } => run_restore(backup_dir, project_dir)?,
     }
+    Ok(())
 }
 
 fn run_extract(
-    input_file: &PathBuf,
-    output_dir: Option<&PathBuf>,
-    overwrite_mode: crate::ui::OverwriteMode,
-) -> Result<(), String> {
-    if !input_file.exists() {
-        return Err(format!("指定されたファイルが存在しません: {}", input_file.display()));
-    }
+    input_file: &Path,
+    output_dir: &Path,
+    overwrite_modes: OverwriteModes,
+    options: &ExtractOptions,
+    format: Option<ManifestFormat>,
+) -> Result<()> {
+    let reader = PackageReader::open(input_file)?;
 
-    let output_dir = output_dir
-        .ok_or_else(|| "--output-dir is required in CLI mode".to_string())?;
+    // マニフェストを出力する場合は標準出力を汚さないよう進捗を表示しない
+    // マニフェストにはassetのハッシュが必要なため、展開とは別にアーカイブを読む
+    if let Some(format) = format {
+        let entries = reader.target_entries(options)?;
+        let mut ui_handler = CliProgressHandler::quiet(overwrite_modes);
+        reader.extract(output_dir, options, &mut ui_handler)?;
 
-    let tmp_output_dir = output_dir.join(TMP_OUTPUT_DIR);
-    if tmp_output_dir.exists() {
-        std::fs::remove_dir_all(&tmp_output_dir)
-            .map_err(|e| format!("一時ディレクトリの削除に失敗しました: {}", e))?;
+        print!("{}", render_manifest(&entries, format));
+        return Ok(());
     }
 
-    // 失敗時も含めて確実に一時ディレクトリを削除するためのガード
-    struct TempDirGuard {
-        path: PathBuf,
+    println!("解凍を開始します: {} -> {}", input_file.display(), output_dir.display());
+
+    let mut ui_handler = CliProgressHandler::new(overwrite_modes);
+    let summary = reader.extract(output_dir, options, &mut ui_handler)?;
+
+    for upgrade_move in &summary.upgrade_moves {
+        println!("移動: {} -> {}", upgrade_move.from, upgrade_move.to);
     }
-    impl Drop for TempDirGuard {
-        fn drop(&mut self) {
-            if self.path.exists() {
-                if let Err(e) = std::fs::remove_dir_all(&self.path) {
-                    eprintln!("警告: 一時ディレクトリの削除に失敗しました: {}", e);
-                }
-            }
-        }
+    println!("解凍が完了しました。");
+    if let Some(backup_dir) = &options.backup_dir
+        && backup_dir.exists()
+    {
+        println!("上書きしたファイルのバックアップ: {}", backup_dir.display());
     }
-    let _tmp_guard = TempDirGuard { path: tmp_output_dir.clone() };
 
-    println!("解凍を開始します: {} -> {}", input_file.display(), output_dir.display());
+    Ok(())
+}
 
-    let mut objects = HashMap::new();
-    let mut ui_handler = CliProgressHandler::new(overwrite_mode);
+fn run_dry_run(
+    input_file: &Path,
+    output_dir: &Path,
+    overwrite_modes: OverwriteModes,
+    options: &ExtractOptions,
+) -> Result<()> {
+    let reader = PackageReader::open(input_file)?;
 
-    // 抽出
-    extract_objects(input_file, &tmp_output_dir, &mut objects, &mut ui_handler)?;
+    let plan = reader.plan_extract(output_dir, options, overwrite_modes)?;
 
-    // 再構築
-    rebuild_objects(&objects, &output_dir, &tmp_output_dir, &mut ui_handler)?;
+    for upgrade_move in &plan.upgrade_moves {
+        let suffix = if upgrade_move.is_folder { "/" } else { "" };
+        println!("移動: {}{} -> {}{}", upgrade_move.from, suffix, upgrade_move.to, suffix);
+    }
 
-    // 明示的なクリーンアップは不要（Dropガードで常に削除される）
+    let mut counts = [0usize; 7];
+    for operation in &plan.operations {
+        let (index, label) = match &operation.action {
+            PlannedAction::Create => (0, "作成"),
+            PlannedAction::Overwrite => (1, "上書き"),
+            PlannedAction::Skip => (2, "スキップ"),
+            PlannedAction::Rename { .. } => (3, "リネーム"),
+            PlannedAction::Ask => (4, "要確認"),
+            PlannedAction::Keep => (5, "既存"),
+            PlannedAction::Identical => (6, "同一"),
+        };
+        counts[index] += 1;
 
-    println!("解凍が完了しました。");
+        let suffix = if operation.is_folder { "/" } else { "" };
+        match &operation.action {
+            PlannedAction::Rename { to } => println!("{}: {} -> {}", label, operation.path, to),
+            _ => println!("{}: {}{}", label, operation.path, suffix),
+        }
+    }
+
+    for conflict in &plan.conflicts {
+        println!("GUID衝突: {}", conflict);
+    }
+
+    let upgrade_summary = if options.upgrade {
+        format!("移動 {}件、", plan.upgrade_moves.len())
+    } else {
+        String::new()
+    };
+    println!(
+        "{}作成 {}件、上書き {}件、スキップ {}件、リネーム {}件、要確認 {}件、既存 {}件、同一 {}件、GUID衝突 {}件（ドライランのため書き込みは行っていません）",
+        upgrade_summary,
+        counts[0],
+        counts[1],
+        counts[2],
+        counts[3],
+        counts[4],
+        counts[5],
+        counts[6],
+        plan.conflicts.len()
+    );
 
     Ok(())
 }
 
 fn run_compress(
-    input_dir: &PathBuf,
-    output_file: &PathBuf,
-    project_root: Option<&PathBuf>,
-) -> Result<(), String> {
+    input_dir: &Path,
+    output_file: &Path,
+    options: &CompressOptions,
+) -> Result<()> {
     if !input_dir.exists() {
-        return Err(format!("指定されたディレクトリが存在しません: {}", input_dir.display()));
+        return Err(Error::InputNotFound { path: input_dir.to_path_buf() });
     }
 
     if !input_dir.is_dir() {
-        return Err(format!("指定されたパスはディレクトリではありません: {}", input_dir.display()));
+        return Err(Error::NotADirectory { path: input_dir.to_path_buf() });
     }
 
     println!("圧縮を開始します: {} -> {}", input_dir.display(), output_file.display());
-    if let Some(root) = project_root {
+    if let Some(root) = &options.project_root {
         println!("プロジェクトルート: {}", root.display());
     }
 
     // 圧縮モードではOverwriteModeは不要（常にRenameで良い）
-    let mut ui_handler = CliProgressHandler::new(crate::ui::OverwriteMode::Rename);
+    let mut ui_handler = CliProgressHandler::new(OverwriteMode::Rename);
 
     // 圧縮実行
-    compress_directory(input_dir, output_file, project_root.map(|p| p.as_path()), &mut ui_handler)?;
+    compress_directory(input_dir, output_file, options, &mut ui_handler)?;
 
     println!("圧縮が完了しました。");
 
     Ok(())
 }
 
+
+fn run_list(input_file: &Path, format: Option<ManifestFormat>) -> Result<()> {
+    let entries = PackageReader::open(input_file)?.entries()?;
+
+    if let Some(format) = format {
+        print!("{}", render_manifest(&entries, format));
+        return Ok(());
+    }
+
+    println!("{:<32}  {:<7}  {:>12}  {:<7}  PATHNAME", "GUID", "TYPE", "SIZE", "PREVIEW");
+    for entry in &entries {
+        let kind = entry.kind_name();
+        let size = entry
+            .asset_size
+            .map(|size| size.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let preview = if entry.has_preview { "yes" } else { "-" };
+        let pathname = entry.pathname.as_deref().unwrap_or("(pathnameなし)");
+        println!("{:<32}  {:<7}  {:>12}  {:<7}  {}", entry.guid, kind, size, preview, pathname);
+    }
+
+    let total_size: u64 = entries.iter().filter_map(|e| e.asset_size).sum();
+    let preview_count = entries.iter().filter(|e| e.has_preview).count();
+    println!(
+        "{}個のエントリ（合計 {} バイト、プレビュー {}件）",
+        entries.len(),
+        total_size,
+        preview_count
+    );
+    let unknown_count = entries.iter().filter(|e| e.kind.is_none()).count();
+    if unknown_count > 0 {
+        eprintln!(
+            "警告: フォルダかファイルか判定できないエントリが{}件あります（verifyで詳細を確認できます）",
+            unknown_count
+        );
+    }
+
+    Ok(())
+}
+
+fn run_verify(input_file: &Path) -> Result<()> {
+    let issues = PackageReader::open(input_file)?.verify()?;
+    if !issues.is_empty() {
+        return Err(Error::VerifyFailed { issues });
+    }
+
+    println!("問題は見つかりませんでした: {}", input_file.display());
+    Ok(())
+}
+
+fn run_diff(old_file: &Path, new_file: &Path, format: DiffFormat) -> Result<()> {
+    let old = PackageReader::open(old_file)?;
+    let new = PackageReader::open(new_file)?;
+
+    let diffs = old.diff(&new)?;
+    print!("{}", render_diff(&diffs, format));
+    Ok(())
+}
+
+fn run_merge(input_files: &[PathBuf], output_file: &Path, policy: MergePolicy) -> Result<()> {
+    for input_file in input_files {
+        if !input_file.exists() {
+            return Err(Error::InputNotFound { path: input_file.clone() });
+        }
+    }
+
+    println!("結合を開始します: {}個のパッケージ -> {}", input_files.len(), output_file.display());
+
+    let mut ui_handler = CliProgressHandler::new(OverwriteMode::Rename);
+    let conflicts = merge_packages(input_files, output_file, policy, &mut ui_handler)?;
+    for conflict in &conflicts {
+        eprintln!("警告: 衝突を解決しました: {}", conflict);
+    }
+
+    println!("結合が完了しました。");
+    Ok(())
+}
+
+fn run_edit(input_file: &Path, output_file: Option<&Path>, edit: &PackageEdit) -> Result<()> {
+    let reader = PackageReader::open(input_file)?;
+
+    let mut ui_handler = CliProgressHandler::quiet(OverwriteMode::Rename);
+    let summary = edit_package(reader.path(), output_file, edit, &mut ui_handler)?;
+
+    let label = match edit {
+        PackageEdit::Remove { .. } => "削除",
+        PackageEdit::Move { .. } => "移動",
+        PackageEdit::Replace { .. } => "置き換え",
+    };
+    for changed in &summary.changed {
+        println!("{}: {}", label, changed);
+    }
+    println!(
+        "{}件のエントリを編集しました: {}",
+        summary.changed.len(),
+        output_file.unwrap_or(input_file).display()
+    );
+    Ok(())
+}
+
+fn run_uninstall(input_file: &Path, project_dir: &Path) -> Result<()> {
+    let reader = PackageReader::open(input_file)?;
+    if !project_dir.is_dir() {
+        return Err(Error::NotADirectory {
+            path: project_dir.to_path_buf(),
+        });
+    }
+
+    let summary = reader.uninstall(project_dir)?;
+    for removed in &summary.removed {
+        println!("削除: {}", removed);
+    }
+    for modified in &summary.modified {
+        eprintln!("警告: 内容が変更されているため残しました: {}", modified);
+    }
+    for folder in &summary.kept_folders {
+        eprintln!("警告: フォルダが空でないため残しました: {}/", folder);
+    }
+    for missing in &summary.missing_assets {
+        eprintln!("警告: assetが見つからないためmetaを残しました: {}.meta", missing);
+    }
+    for unknown in &summary.unknown_kind {
+        eprintln!("警告: フォルダかファイルか判定できないため対象外にしました: {}", unknown);
+    }
+    println!(
+        "削除 {}件、変更ありで残したファイル {}件、空でないため残したフォルダ {}件",
+        summary.removed.len(),
+        summary.modified.len(),
+        summary.kept_folders.len()
+    );
+    Ok(())
+}
+
+fn run_restore(backup_dir: &Path, project_dir: &Path) -> Result<()> {
+    if !project_dir.is_dir() {
+        return Err(Error::NotADirectory {
+            path: project_dir.to_path_buf(),
+        });
+    }
+
+    let restored = restore_backup(backup_dir, project_dir)?;
+    for pathname in &restored {
+        println!("復元: {}", pathname);
+    }
+    println!("{}件のファイルを復元しました: {}", restored.len(), project_dir.display());
+    Ok(())
+}
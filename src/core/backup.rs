@@ -0,0 +1,137 @@
+use crate::core::error::{Error, IoOperation, Result};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// バックアップのルートディレクトリに、今回の展開用のタイムスタンプ付きディレクトリのパスを決める
+/// （`<root>/20240102-030405`、同じ秒に複数回実行した場合は `-2` などを付ける）
+/// ディレクトリは実際に上書きが発生したときに作成する。
+pub fn backup_session_dir(root: &Path) -> PathBuf {
+    let timestamp = format_timestamp(SystemTime::now());
+    let mut dir = root.join(&timestamp);
+    let mut n = 2;
+    while dir.exists() {
+        dir = root.join(format!("{}-{}", timestamp, n));
+        n += 1;
+    }
+    dir
+}
+
+/// UTCの `YYYYMMDD-HHMMSS` 形式
+fn format_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86400, secs % 86400);
+
+    // 1970-01-01からの日数をグレゴリオ暦の日付に変換
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// 上書きする前に既存のファイルをバックアップディレクトリに移動する
+/// バックアップ内のパスは出力先ディレクトリからの相対パスと同じにする
+pub(crate) fn backup_file(backup_dir: &Path, output_dir: &Path, path: &Path) -> Result<()> {
+    // 出力先の外のパス（--allow-unsafe-paths）でもバックアップの外に書き込まないよう、通常の要素だけを使う
+    let relative = path.strip_prefix(output_dir).unwrap_or(path);
+    let relative: PathBuf = relative
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    move_file(path, &backup_dir.join(relative))
+}
+
+/// バックアップしたファイルをプロジェクトの元の場所に戻す
+///
+/// `backup_dir` は `--backup-dir` の下に作成されたタイムスタンプ付きのディレクトリ。
+/// 現在のファイルは上書きされる。戻したファイルのプロジェクトからの相対パスを返し、
+/// 空になったバックアップディレクトリは削除する。
+pub fn restore_backup(backup_dir: &Path, project_dir: &Path) -> Result<Vec<String>> {
+    if !backup_dir.is_dir() {
+        return Err(Error::NotADirectory {
+            path: backup_dir.to_path_buf(),
+        });
+    }
+
+    let mut restored = Vec::new();
+    restore_dir(backup_dir, project_dir, "", &mut restored)?;
+    restored.sort();
+    Ok(restored)
+}
+
+fn restore_dir(dir: &Path, project_dir: &Path, relative: &str, restored: &mut Vec<String>) -> Result<()> {
+    let read_dir = std::fs::read_dir(dir).map_err(Error::io(IoOperation::ReadDir, dir))?;
+    for entry in read_dir {
+        let entry = entry.map_err(Error::io(IoOperation::ReadDir, dir))?;
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let child = if relative.is_empty() {
+            file_name
+        } else {
+            format!("{}/{}", relative, file_name)
+        };
+
+        if path.is_dir() {
+            restore_dir(&path, project_dir, &child, restored)?;
+        } else {
+            move_file(&path, &project_dir.join(&child))?;
+            restored.push(child);
+        }
+    }
+    std::fs::remove_dir(dir).map_err(Error::io(IoOperation::Remove, dir))
+}
+
+/// ファイルを移動する（別のファイルシステムへはコピーしてから削除する）
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent).map_err(Error::io(IoOperation::CreateDir, parent))?;
+    }
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to).map_err(Error::io(IoOperation::Write, to))?;
+    std::fs::remove_file(from).map_err(Error::io(IoOperation::Remove, from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(secs: u64) -> String {
+        format_timestamp(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn format_timestamp_formats_utc_dates() {
+        assert_eq!(at(0), "19700101-000000");
+        assert_eq!(at(1704164645), "20240102-030405");
+    }
+
+    #[test]
+    fn format_timestamp_handles_leap_years() {
+        // 2000年は400で割り切れるため閏年、2100年は閏年ではない
+        assert_eq!(at(951782400), "20000229-000000");
+        assert_eq!(at(4107542399), "21000228-235959");
+        assert_eq!(at(4107542400), "21000301-000000");
+    }
+
+    #[test]
+    fn format_timestamp_clamps_times_before_the_epoch() {
+        assert_eq!(format_timestamp(UNIX_EPOCH - Duration::from_secs(1)), "19700101-000000");
+    }
+}
@@ -1,20 +1,27 @@
+use crate::core::entry::{EntryKind, PackageEntry};
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::writer::PackageWriter;
 use crate::ui::UiHandler;
-use flate2::write::GzEncoder;
-use flate2::Compression;
-use std::fs::File;
 use std::path::{Path, PathBuf};
-use tar::Builder;
 use yaml_rust::YamlLoader;
 
+/// 圧縮時のオプション
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompressOptions {
+    /// pathnameの基準にするプロジェクトルート
+    /// 省略時は入力ディレクトリの親ディレクトリを使用する
+    pub project_root: Option<PathBuf>,
+}
+
 /// Unityプロジェクトディレクトリから.unitypackageファイルを生成
 pub fn compress_directory<U: UiHandler>(
     input_dir: &Path,
     output_file: &Path,
-    project_root: Option<&Path>,
+    options: &CompressOptions,
     ui_handler: &mut U,
-) -> Result<(), String> {
+) -> Result<()> {
     // プロジェクトルートの決定
-    let base_dir = if let Some(root) = project_root {
+    let base_dir = if let Some(root) = options.project_root.as_deref() {
         root
     } else {
         input_dir.parent().unwrap_or(input_dir)
@@ -24,17 +31,13 @@ pub fn compress_directory<U: UiHandler>(
     let entries = collect_entries(input_dir, base_dir, ui_handler)?;
 
     if entries.is_empty() {
-        return Err("圧縮対象のファイルが見つかりませんでした".to_string());
+        return Err(Error::NoEntries { path: input_dir.to_path_buf() });
     }
 
     println!("{}個のファイルを圧縮します...", entries.len());
 
     // 出力ファイルを作成
-    let output_file_handle = File::create(output_file)
-        .map_err(|e| format!("出力ファイルの作成に失敗しました: {}", e))?;
-
-    let gz_encoder = GzEncoder::new(output_file_handle, Compression::default());
-    let mut tar_builder = Builder::new(gz_encoder);
+    let mut writer = PackageWriter::create(output_file)?;
 
     let total = entries.len() as f32;
 
@@ -42,7 +45,7 @@ pub fn compress_directory<U: UiHandler>(
     for (idx, entry) in entries.iter().enumerate() {
         // キャンセルチェック
         if ui_handler.is_cancelled() {
-            return Err("キャンセルされました".to_string());
+            return Err(Error::Cancelled);
         }
 
         ui_handler.update_progress(
@@ -50,48 +53,47 @@ pub fn compress_directory<U: UiHandler>(
             &entry.pathname
         );
 
-        add_entry_to_archive(&mut tar_builder, entry)?;
+        writer.append_entry(
+            &entry.guid,
+            &entry.pathname,
+            &entry.meta,
+            entry.asset_location.as_deref(),
+        )?;
     }
 
     // アーカイブを完了
-    tar_builder.finish()
-        .map_err(|e| format!("アーカイブの完了に失敗しました: {}", e))?;
+    writer.finish()?;
 
     ui_handler.finish();
     Ok(())
 }
 
-/// アーカイブエントリ情報
-struct ArchiveEntry {
-    guid: String,
-    pathname: String,
-    asset_path: Option<PathBuf>,
-    meta_content: String,
-}
-
 /// 入力ディレクトリから圧縮対象のエントリを収集
 fn collect_entries<U: UiHandler>(
     input_dir: &Path,
     base_dir: &Path,
     ui_handler: &mut U,
-) -> Result<Vec<ArchiveEntry>, String> {
+) -> Result<Vec<PackageEntry>> {
     let mut entries = Vec::new();
 
     // まず、ルートディレクトリ自体をエントリとして追加
     let meta_path = PathBuf::from(format!("{}.meta", input_dir.display()));
     if meta_path.exists() {
         let meta_content = std::fs::read_to_string(&meta_path)
-            .map_err(|e| format!("ルートディレクトリのmetaファイルの読み込みに失敗しました: {}", e))?;
+            .map_err(Error::io(IoOperation::Read, &meta_path))?;
 
-        let guid = extract_guid_from_meta(&meta_content)?;
+        let guid = extract_guid_from_meta(&meta_content, &meta_path)?;
         let pathname = get_relative_path(base_dir, input_dir)?;
 
         // ルートディレクトリをフォルダとして追加
-        entries.push(ArchiveEntry {
+        entries.push(PackageEntry {
             guid,
             pathname,
-            asset_path: None,
-            meta_content,
+            meta: meta_content,
+            kind: EntryKind::Folder,
+            asset_location: None,
+            asset_size: None,
+            preview: None,
         });
     }
 
@@ -105,20 +107,20 @@ fn collect_entries<U: UiHandler>(
 fn collect_entries_recursive<U: UiHandler>(
     base_dir: &Path,
     current_dir: &Path,
-    entries: &mut Vec<ArchiveEntry>,
+    entries: &mut Vec<PackageEntry>,
     ui_handler: &mut U,
-) -> Result<(), String> {
+) -> Result<()> {
     let read_dir = std::fs::read_dir(current_dir)
-        .map_err(|e| format!("ディレクトリの読み込みに失敗しました: {}", e))?;
+        .map_err(Error::io(IoOperation::ReadDir, current_dir))?;
 
     for entry_result in read_dir {
         // キャンセルチェック
         if ui_handler.is_cancelled() {
-            return Err("キャンセルされました".to_string());
+            return Err(Error::Cancelled);
         }
 
         let entry = entry_result
-            .map_err(|e| format!("ディレクトリエントリの読み込みに失敗しました: {}", e))?;
+            .map_err(Error::io(IoOperation::ReadDir, current_dir))?;
 
         let path = entry.path();
         let file_name = entry.file_name();
@@ -150,9 +152,9 @@ fn collect_entries_recursive<U: UiHandler>(
 
         // metaファイルからGUIDを読み取る
         let meta_content = std::fs::read_to_string(&meta_path)
-            .map_err(|e| format!("metaファイルの読み込みに失敗しました: {}", e))?;
+            .map_err(Error::io(IoOperation::Read, &meta_path))?;
 
-        let guid = extract_guid_from_meta(&meta_content)?;
+        let guid = extract_guid_from_meta(&meta_content, &meta_path)?;
         let pathname = get_relative_path(base_dir, &path)?;
 
         // フォルダかどうかの判定:
@@ -163,11 +165,14 @@ fn collect_entries_recursive<U: UiHandler>(
         let is_folder = is_folder_by_fs || is_folder_by_meta;
 
         if is_folder {
-            entries.push(ArchiveEntry {
+            entries.push(PackageEntry {
                 guid,
                 pathname,
-                asset_path: None,
-                meta_content,
+                meta: meta_content,
+                kind: EntryKind::Folder,
+                asset_location: None,
+                asset_size: None,
+                preview: None,
             });
 
             // ディレクトリの場合は内部を再帰的に走査
@@ -176,11 +181,14 @@ fn collect_entries_recursive<U: UiHandler>(
             }
         } else {
             // ファイルの場合
-            entries.push(ArchiveEntry {
+            entries.push(PackageEntry {
                 guid,
                 pathname,
-                asset_path: Some(path.clone()),
-                meta_content,
+                meta: meta_content,
+                kind: EntryKind::File,
+                asset_size: std::fs::metadata(&path).ok().map(|metadata| metadata.len()),
+                asset_location: Some(path.clone()),
+                preview: None,
             });
         }
     }
@@ -189,23 +197,23 @@ fn collect_entries_recursive<U: UiHandler>(
 }
 
 /// metaファイルからGUIDを抽出
-fn extract_guid_from_meta(meta_content: &str) -> Result<String, String> {
+fn extract_guid_from_meta(meta_content: &str, meta_path: &Path) -> Result<String> {
     let docs = YamlLoader::load_from_str(meta_content)
-        .map_err(|e| format!("metaファイルのパースに失敗しました: {}", e))?;
+        .map_err(|e| Error::MetaParse { path: meta_path.display().to_string(), reason: e.to_string() })?;
 
-    let doc = docs.get(0)
-        .ok_or("metaファイルのルートが見つかりません")?;
+    let doc = docs.first()
+        .ok_or_else(|| Error::MetaParse { path: meta_path.display().to_string(), reason: "ルートが見つかりません".to_string() })?;
 
     let guid = doc["guid"].as_str()
-        .ok_or("metaファイルにguidフィールドが見つかりません")?;
+        .ok_or_else(|| Error::MissingGuid { path: meta_path.to_path_buf() })?;
 
     Ok(guid.to_string())
 }
 
 /// ベースディレクトリからの相対パスを取得
-fn get_relative_path(base_dir: &Path, path: &Path) -> Result<String, String> {
+fn get_relative_path(base_dir: &Path, path: &Path) -> Result<String> {
     let relative = path.strip_prefix(base_dir)
-        .map_err(|e| format!("相対パスの取得に失敗しました: {}", e))?;
+        .map_err(|_| Error::OutsideBaseDir { base: base_dir.to_path_buf(), path: path.to_path_buf() })?;
 
     // Unixスタイルのパス区切りに変換
     let pathname = relative.to_string_lossy()
@@ -214,67 +222,12 @@ fn get_relative_path(base_dir: &Path, path: &Path) -> Result<String, String> {
     Ok(pathname)
 }
 
-/// エントリをアーカイブに追加
-fn add_entry_to_archive(
-    tar_builder: &mut Builder<GzEncoder<File>>,
-    entry: &ArchiveEntry,
-) -> Result<(), String> {
-    let guid = &entry.guid;
-
-    // pathname ファイルを追加
-    {
-        let pathname_path = format!("{}/pathname", guid);
-        let pathname_data = entry.pathname.as_bytes();
-
-        let mut header = tar::Header::new_gnu();
-        header.set_path(&pathname_path)
-            .map_err(|e| format!("pathnameパスの設定に失敗しました: {}", e))?;
-        header.set_size(pathname_data.len() as u64);
-        header.set_mode(0o644);
-        header.set_cksum();
-
-        tar_builder.append(&header, pathname_data)
-            .map_err(|e| format!("pathnameの追加に失敗しました: {}", e))?;
-    }
-
-    // asset.meta ファイルを追加
-    {
-        let meta_path = format!("{}/asset.meta", guid);
-        let meta_data = entry.meta_content.as_bytes();
-
-        let mut header = tar::Header::new_gnu();
-        header.set_path(&meta_path)
-            .map_err(|e| format!("asset.metaパスの設定に失敗しました: {}", e))?;
-        header.set_size(meta_data.len() as u64);
-        header.set_mode(0o644);
-        header.set_cksum();
-
-        tar_builder.append(&header, meta_data)
-            .map_err(|e| format!("asset.metaの追加に失敗しました: {}", e))?;
-    }
-
-    // asset ファイルを追加（ファイルの場合のみ）
-    if let Some(asset_path) = &entry.asset_path {
-        // ファイルの場合は実ファイルを追加
-        let asset_archive_path = format!("{}/asset", guid);
-
-        let mut file = File::open(asset_path)
-            .map_err(|e| format!("アセットファイルのオープンに失敗しました: {}", e))?;
-
-        tar_builder.append_file(&asset_archive_path, &mut file)
-            .map_err(|e| format!("アセットファイルの追加に失敗しました: {}", e))?;
-    }
-    // フォルダーの場合はassetファイルを生成しない
-
-    Ok(())
-}
-
 /// metaファイルにfolderAsset: yesが含まれているか確認
 fn check_folder_asset_in_meta(meta_content: &str) -> bool {
-    if let Ok(docs) = YamlLoader::load_from_str(meta_content) {
-        if let Some(doc) = docs.get(0) {
-            return doc["folderAsset"].as_str().unwrap_or("false") == "yes";
-        }
+    if let Ok(docs) = YamlLoader::load_from_str(meta_content)
+        && let Some(doc) = docs.first()
+    {
+        return doc["folderAsset"].as_str().unwrap_or("false") == "yes";
     }
     false
 }
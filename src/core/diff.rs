@@ -0,0 +1,277 @@
+use crate::core::list::ListEntry;
+use crate::core::manifest::{json_optional_string, json_string};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// 2つのパッケージ間のエントリの変更の種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffKind {
+    /// 新しいパッケージで追加された
+    Added,
+    /// 新しいパッケージで削除された
+    Removed,
+    /// 同じGUIDのままpathnameが変わった
+    Moved { from: String },
+    /// assetの内容（SHA-256）が変わった
+    ContentChanged,
+    /// asset.metaの内容が変わった
+    MetaChanged,
+}
+
+impl DiffKind {
+    fn label(&self) -> &'static str {
+        match self {
+            DiffKind::Added => "追加",
+            DiffKind::Removed => "削除",
+            DiffKind::Moved { .. } => "移動",
+            DiffKind::ContentChanged => "内容変更",
+            DiffKind::MetaChanged => "meta変更",
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            DiffKind::Added => "added",
+            DiffKind::Removed => "removed",
+            DiffKind::Moved { .. } => "moved",
+            DiffKind::ContentChanged => "content_changed",
+            DiffKind::MetaChanged => "meta_changed",
+        }
+    }
+}
+
+/// 1エントリ分の変更（移動と内容変更が同時にある場合は別々に記録する）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub guid: String,
+    /// 新しいパッケージでのpathname（削除の場合は古いパッケージでのpathname）
+    pub pathname: String,
+    pub kind: DiffKind,
+}
+
+/// 差分の出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffFormat {
+    /// 1行に1つの変更を表示するテキスト
+    #[default]
+    Text,
+    /// 変更ごとのオブジェクトを並べたJSON配列
+    Json,
+}
+
+impl DiffFormat {
+    /// コマンドライン引数の値から変換
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(DiffFormat::Text),
+            "json" => Some(DiffFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// 2つのパッケージのエントリ一覧をGUIDで対応付けて比較する
+/// pathnameのないエントリは対応付けられないため対象外とする
+pub fn diff_entries(old: &[ListEntry], new: &[ListEntry]) -> Vec<DiffEntry> {
+    let old_by_guid: HashMap<&str, &ListEntry> = old
+        .iter()
+        .filter(|entry| entry.pathname.is_some())
+        .map(|entry| (entry.guid.as_str(), entry))
+        .collect();
+    let new_by_guid: HashMap<&str, &ListEntry> = new
+        .iter()
+        .filter(|entry| entry.pathname.is_some())
+        .map(|entry| (entry.guid.as_str(), entry))
+        .collect();
+
+    let mut diffs = Vec::new();
+    for (guid, new_entry) in &new_by_guid {
+        let pathname = new_entry.pathname.clone().unwrap_or_default();
+        let mut push = |kind| {
+            diffs.push(DiffEntry {
+                guid: guid.to_string(),
+                pathname: pathname.clone(),
+                kind,
+            })
+        };
+
+        let Some(old_entry) = old_by_guid.get(guid) else {
+            push(DiffKind::Added);
+            continue;
+        };
+        if old_entry.pathname != new_entry.pathname {
+            push(DiffKind::Moved {
+                from: old_entry.pathname.clone().unwrap_or_default(),
+            });
+        }
+        if old_entry.sha256 != new_entry.sha256 {
+            push(DiffKind::ContentChanged);
+        }
+        if old_entry.meta != new_entry.meta {
+            push(DiffKind::MetaChanged);
+        }
+    }
+    for (guid, old_entry) in &old_by_guid {
+        if !new_by_guid.contains_key(guid) {
+            diffs.push(DiffEntry {
+                guid: guid.to_string(),
+                pathname: old_entry.pathname.clone().unwrap_or_default(),
+                kind: DiffKind::Removed,
+            });
+        }
+    }
+
+    diffs.sort_by(|a, b| a.pathname.cmp(&b.pathname).then_with(|| a.guid.cmp(&b.guid)));
+    diffs
+}
+
+/// 差分を指定の形式で文字列にする
+pub fn render_diff(diffs: &[DiffEntry], format: DiffFormat) -> String {
+    match format {
+        DiffFormat::Text => render_text(diffs),
+        DiffFormat::Json => render_json(diffs),
+    }
+}
+
+fn render_text(diffs: &[DiffEntry]) -> String {
+    let mut out = String::new();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for diff in diffs {
+        *counts.entry(diff.kind.key()).or_default() += 1;
+        match &diff.kind {
+            DiffKind::Moved { from } => {
+                let _ = writeln!(out, "{}: {} -> {} [{}]", diff.kind.label(), from, diff.pathname, diff.guid);
+            }
+            kind => {
+                let _ = writeln!(out, "{}: {} [{}]", kind.label(), diff.pathname, diff.guid);
+            }
+        }
+    }
+
+    let count = |key| counts.get(key).copied().unwrap_or(0);
+    let _ = writeln!(
+        out,
+        "追加 {}件、削除 {}件、移動 {}件、内容変更 {}件、meta変更 {}件",
+        count("added"),
+        count("removed"),
+        count("moved"),
+        count("content_changed"),
+        count("meta_changed")
+    );
+    out
+}
+
+fn render_json(diffs: &[DiffEntry]) -> String {
+    let mut out = String::from("[\n");
+    for (idx, diff) in diffs.iter().enumerate() {
+        let from = match &diff.kind {
+            DiffKind::Moved { from } => Some(from.as_str()),
+            _ => None,
+        };
+        let separator = if idx + 1 < diffs.len() { "," } else { "" };
+        let _ = writeln!(
+            out,
+            "  {{\"guid\": {}, \"change\": {}, \"pathname\": {}, \"previous_pathname\": {}}}{}",
+            json_string(&diff.guid),
+            json_string(diff.kind.key()),
+            json_string(&diff.pathname),
+            json_optional_string(from),
+            separator
+        );
+    }
+    out.push_str("]\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(guid: &str, pathname: &str, sha256: &str) -> ListEntry {
+        ListEntry {
+            guid: guid.to_string(),
+            pathname: Some(pathname.to_string()),
+            sha256: Some(sha256.to_string()),
+            meta: Some(format!("guid: {}\n", guid)),
+            ..Default::default()
+        }
+    }
+
+    fn diff(guid: &str, pathname: &str, kind: DiffKind) -> DiffEntry {
+        DiffEntry {
+            guid: guid.to_string(),
+            pathname: pathname.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn detects_added_removed_moved_and_changed_entries() {
+        let old = [
+            entry("01", "Assets/kept.txt", "aa"),
+            entry("02", "Assets/removed.txt", "bb"),
+            entry("03", "Assets/old.txt", "cc"),
+            entry("04", "Assets/changed.txt", "dd"),
+        ];
+        let new = [
+            entry("01", "Assets/kept.txt", "aa"),
+            entry("03", "Assets/new.txt", "cc"),
+            entry("04", "Assets/changed.txt", "ee"),
+            entry("05", "Assets/added.txt", "ff"),
+        ];
+
+        assert_eq!(
+            diff_entries(&old, &new),
+            [
+                diff("05", "Assets/added.txt", DiffKind::Added),
+                diff("04", "Assets/changed.txt", DiffKind::ContentChanged),
+                diff(
+                    "03",
+                    "Assets/new.txt",
+                    DiffKind::Moved {
+                        from: "Assets/old.txt".to_string()
+                    }
+                ),
+                diff("02", "Assets/removed.txt", DiffKind::Removed),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_move_and_content_change_separately() {
+        let old = [entry("01", "Assets/a.txt", "aa")];
+        let new = [entry("01", "Assets/b.txt", "bb")];
+
+        let kinds: Vec<DiffKind> = diff_entries(&old, &new).into_iter().map(|diff| diff.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DiffKind::Moved {
+                    from: "Assets/a.txt".to_string()
+                },
+                DiffKind::ContentChanged
+            ]
+        );
+    }
+
+    #[test]
+    fn json_escapes_pathnames() {
+        let diffs = [diff(
+            "01",
+            "Assets/b \"quoted\"\\.txt",
+            DiffKind::Moved {
+                from: "Assets/a\n.txt".to_string(),
+            },
+        )];
+
+        assert_eq!(
+            render_diff(&diffs, DiffFormat::Json),
+            "[\n  {\"guid\": \"01\", \"change\": \"moved\", \"pathname\": \"Assets/b \\\"quoted\\\"\\\\.txt\", \
+             \"previous_pathname\": \"Assets/a\\n.txt\"}\n]\n"
+        );
+        assert_eq!(
+            render_diff(&[diff("02", "Assets/c.txt", DiffKind::Added)], DiffFormat::Json),
+            "[\n  {\"guid\": \"02\", \"change\": \"added\", \"pathname\": \"Assets/c.txt\", \"previous_pathname\": null}\n]\n"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use crate::core::entry::entry_kind;
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::extract::{
+    ExtractOptions, ProgressReader, ASSET_FILE_NAME, ASSET_META_FILENAME, PATHNAME_FILENAME, PREVIEW_FILENAME,
+};
+use crate::core::guid::{is_text_serialized, GuidRemap};
+use crate::core::path::{archive_entry_depth, split_archive_entry_path};
+use crate::core::preview::export_preview;
+use crate::core::rebuild::{handle_directory, handle_file, rewrite_renamed_references, AssetSource};
+use crate::ui::UiHandler;
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use tar::Archive;
+
+/// assetがpathnameより先に現れた場合の退避先ディレクトリ
+const SPILL_DIR: &str = ".jp.ootr.unitypackage-extractor.spill";
+
+/// 展開途中のエントリ
+#[derive(Default)]
+struct PendingEntry {
+    pathname: Option<String>,
+    meta: Option<String>,
+    /// pathnameより先に届いたassetの退避先
+    spill: Option<PathBuf>,
+    /// assetを配置済み（またはスキップ済み）
+    asset_done: bool,
+    /// 付け替え後のpathname（付け替え先がない場合はNone）
+    target: Option<String>,
+    /// preview.pngの内容（書き出し先が指定されている場合のみ保持）
+    preview: Option<Vec<u8>>,
+}
+
+/// 一時ディレクトリを経由せず、assetを最終的な出力先に直接書き込む
+///
+/// pathnameとasset.metaはGUIごとにメモリに保持し、assetが届いた時点で
+/// 出力先が分かっていればそのまま書き込む。pathnameより先にassetが届いた場合のみ
+/// 出力先ディレクトリ内の退避ファイルに書き出し、pathnameが届いた時点で移動する。
+///
+/// 読み込みと書き込みを同時に行うため、ここではpathnameを検証しない。安全でないpathnameが
+/// 1つでもあれば何も書き込まないよう、呼び出し側でアーカイブを一度読んで検証しておくこと
+/// （`PackageReader::preflight`）。`remap` には `options.remap_guids` の場合の対応表を渡す。
+pub fn extract_direct<U: UiHandler>(
+    archive_path: &Path,
+    output_dir: &Path,
+    options: &ExtractOptions,
+    remap: Option<&GuidRemap>,
+    ui_handler: &mut U,
+) -> Result<()> {
+    let filter = &options.filter;
+    if !output_dir.exists() {
+        std::fs::create_dir_all(output_dir).map_err(Error::io(IoOperation::CreateDir, output_dir))?;
+    }
+
+    // 失敗時も含めて確実に退避ディレクトリを削除するためのガード
+    struct SpillDirGuard {
+        path: PathBuf,
+    }
+    impl Drop for SpillDirGuard {
+        fn drop(&mut self) {
+            if self.path.exists()
+                && let Err(e) = std::fs::remove_dir_all(&self.path)
+            {
+                eprintln!("警告: 一時ディレクトリの削除に失敗しました: {}", e);
+            }
+        }
+    }
+    let spill_dir = output_dir.join(SPILL_DIR);
+    let _spill_guard = SpillDirGuard { path: spill_dir.clone() };
+
+    let file = File::open(archive_path).map_err(Error::io(IoOperation::Open, archive_path))?;
+    let total = file
+        .metadata()
+        .map_err(Error::io(IoOperation::Metadata, archive_path))?
+        .len()
+        .max(1);
+    let (reader, bytes_read) = ProgressReader::new(file);
+    let gz = GzDecoder::new(BufReader::new(reader));
+    let mut archive = Archive::new(gz);
+
+    let mut pending: HashMap<String, PendingEntry> = HashMap::new();
+    let mut copies = Vec::new();
+
+    for entry in archive
+        .entries()
+        .map_err(Error::archive(archive_path))?
+    {
+        // キャンセルチェック
+        if ui_handler.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        let mut entry = entry.map_err(Error::archive(archive_path))?;
+        let path = entry
+            .path()
+            .map_err(Error::archive(archive_path))?
+            .to_path_buf();
+
+        // GUIDフォルダなどのディレクトリのエントリは読み飛ばす
+        if !entry.header().entry_type().is_file() || archive_entry_depth(&path) < 2 {
+            continue;
+        }
+        let Some((guid, file_name)) = split_archive_entry_path(&path) else {
+            return Err(Error::MalformedArchive {
+                archive: archive_path.to_path_buf(),
+                entry: Some(path),
+                reason: "エントリは<GUID>/<ファイル名>の形式である必要があります".to_string(),
+            });
+        };
+        let state = pending.entry(guid.clone()).or_default();
+
+        match file_name.as_str() {
+            PATHNAME_FILENAME | ASSET_META_FILENAME => {
+                let mut content = String::new();
+                entry
+                    .read_to_string(&mut content)
+                    .map_err(Error::archive(archive_path))?;
+                if file_name == PATHNAME_FILENAME {
+                    state.target = options.relocation.apply(&content);
+                    state.pathname = Some(content);
+                } else {
+                    state.meta = Some(match remap {
+                        Some(remap) => remap.remap_meta(&content),
+                        None => content,
+                    });
+                }
+
+                // 退避済みのassetの出力先が確定したら移動する
+                if let (Some(pathname), Some(meta)) = (&state.pathname, &state.meta)
+                    && let Some(spill) = state.spill.take()
+                {
+                    ui_handler.update_progress(
+                        (bytes_read.get() as f32) / (total as f32),
+                        pathname,
+                    );
+                    entry_kind(&guid, pathname, meta, true)?;
+                    if filter.matches(pathname)
+                        && let Some(target) = &state.target
+                    {
+                        if let Some(remap) = remap
+                            && is_text_serialized(target)
+                        {
+                            let content = std::fs::read(&spill).map_err(Error::io(IoOperation::Read, &spill))?;
+                            if let Some(remapped) = remap.remap_asset(target, &content) {
+                                std::fs::write(&spill, remapped).map_err(Error::io(IoOperation::Write, &spill))?;
+                            }
+                        }
+                        copies.extend(handle_file(
+                            output_dir,
+                            target,
+                            meta,
+                            AssetSource::File(&spill),
+                            options,
+                            ui_handler,
+                        )?);
+                    }
+                    if spill.exists() {
+                        std::fs::remove_file(&spill).map_err(Error::io(IoOperation::Remove, &spill))?;
+                    }
+                    state.asset_done = true;
+                }
+            }
+            ASSET_FILE_NAME => {
+                if let (Some(pathname), Some(meta)) = (&state.pathname, &state.meta) {
+                    ui_handler.update_progress(
+                        (bytes_read.get() as f32) / (total as f32),
+                        pathname,
+                    );
+                    entry_kind(&guid, pathname, meta, true)?;
+                    if filter.matches(pathname)
+                        && let Some(target) = &state.target
+                    {
+                        // 参照を書き換えるテキスト形式のアセットのみメモリに読み込む
+                        let remapped = match remap {
+                            Some(remap) if is_text_serialized(target) => {
+                                let mut content = Vec::new();
+                                entry
+                                    .read_to_end(&mut content)
+                                    .map_err(Error::archive(archive_path))?;
+                                Some(remap.remap_asset(target, &content).unwrap_or(content))
+                            }
+                            _ => None,
+                        };
+                        let source = match &remapped {
+                            Some(content) => AssetSource::Data(content),
+                            None => {
+                                let size = entry.size();
+                                AssetSource::Reader(&mut entry, size)
+                            }
+                        };
+                        copies.extend(handle_file(output_dir, target, meta, source, options, ui_handler)?);
+                    }
+                    state.asset_done = true;
+                    continue;
+                }
+
+                if let Some(pathname) = &state.pathname
+                    && (!filter.matches(pathname) || state.target.is_none())
+                {
+                    state.asset_done = true;
+                    continue;
+                }
+
+                if !spill_dir.exists() {
+                    std::fs::create_dir_all(&spill_dir).map_err(Error::io(IoOperation::CreateDir, &spill_dir))?;
+                }
+                let spill = spill_dir.join(&guid);
+                let mut spill_file = File::create(&spill).map_err(Error::io(IoOperation::Create, &spill))?;
+                std::io::copy(&mut entry, &mut spill_file).map_err(Error::io(IoOperation::Write, &spill))?;
+                state.spill = Some(spill);
+            }
+            // プレビュー画像は小さいため、対象の判定ができる最後まで保持する
+            PREVIEW_FILENAME => {
+                if options.export_previews.is_some() {
+                    let mut preview = Vec::new();
+                    entry
+                        .read_to_end(&mut preview)
+                        .map_err(Error::archive(archive_path))?;
+                    state.preview = Some(preview);
+                }
+            }
+            _ => {
+                eprintln!("unknown file: {}", file_name);
+            }
+        }
+    }
+
+    // assetを持たないエントリはmetaでフォルダと確認できたものだけを最後にまとめて作成する
+    let pathnames: HashMap<String, String> = pending
+        .iter()
+        .filter_map(|(guid, state)| state.pathname.clone().map(|pathname| (guid.clone(), pathname)))
+        .collect();
+    let selected = filter.select(&pathnames);
+
+    for (guid, state) in &pending {
+        if let (Some(preview_dir), Some(preview), Some(target)) =
+            (&options.export_previews, &state.preview, &state.target)
+            && selected.contains(guid)
+        {
+            export_preview(preview_dir, target, preview)?;
+        }
+
+        if state.asset_done {
+            continue;
+        }
+        let pathname = state
+            .pathname
+            .as_ref()
+            .ok_or_else(|| Error::MissingPathname { guid: guid.clone() })?;
+        let meta = state.meta.as_ref().ok_or_else(|| Error::MissingMeta {
+            guid: guid.clone(),
+            pathname: pathname.clone(),
+        })?;
+        entry_kind(guid, pathname, meta, false)?;
+        if !selected.contains(guid) {
+            continue;
+        }
+        if let Some(target) = &state.target {
+            handle_directory(output_dir, target, meta)?;
+        }
+    }
+
+    if options.rewrite_renamed_references && !copies.is_empty() {
+        rewrite_renamed_references(&copies)?;
+    }
+
+    ui_handler.finish();
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use crate::core::entry::EntryKind;
+use crate::core::error::{Error, IoOperation, Result, UnsafePathEntry};
+use crate::core::extract::{ASSET_FILE_NAME, PATHNAME_FILENAME};
+use crate::core::filter::PathFilter;
+use crate::core::list::{list_entries, ListEntry};
+use crate::core::path::{split_archive_entry_path, validate_pathname};
+use crate::core::writer::PackageWriter;
+use crate::ui::UiHandler;
+use flate2::read::GzDecoder;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use tar::Archive;
+
+/// パッケージに対する編集操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageEdit {
+    /// pathnameがglobにマッチするエントリを削除（フォルダにマッチした場合は中身も削除）
+    Remove { patterns: Vec<String> },
+    /// `from` とその中身のpathnameを `to` 以下に変更
+    Move { from: String, to: String },
+    /// GUIDのエントリのassetをファイルの内容で置き換える
+    Replace { guid: String, file: PathBuf },
+}
+
+/// 編集の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditSummary {
+    /// 削除・移動・置き換えしたエントリのpathname
+    pub changed: Vec<String>,
+}
+
+/// プロジェクトに展開せずにパッケージを編集する
+///
+/// `output_file` が `None` の場合は入力ファイルを置き換える。
+/// 書き込み前にすべての操作を検証し、失敗した場合は入力ファイルを変更しない。
+pub fn edit_package<U: UiHandler>(
+    input_file: &Path,
+    output_file: Option<&Path>,
+    edit: &PackageEdit,
+    ui_handler: &mut U,
+) -> Result<EditSummary> {
+    ui_handler.update_progress(0.0, "エントリを確認しています");
+    let entries = list_entries(input_file)?;
+    let plan = EditPlan::new(&entries, edit)?;
+
+    // 入力ファイルを置き換える場合は一時ファイルに書き込んでから移動する
+    let output_file = output_file.filter(|path| *path != input_file);
+    let temp_file = output_file.is_none().then(|| {
+        let mut name = input_file.as_os_str().to_os_string();
+        name.push(".tmp");
+        PathBuf::from(name)
+    });
+    let write_path = output_file.or(temp_file.as_deref()).unwrap();
+
+    let result = plan.write(input_file, write_path, ui_handler);
+    if let Err(e) = result {
+        if let Some(temp_file) = &temp_file
+            && temp_file.exists()
+        {
+            let _ = std::fs::remove_file(temp_file);
+        }
+        return Err(e);
+    }
+    if let Some(temp_file) = &temp_file {
+        std::fs::rename(temp_file, input_file).map_err(Error::io(IoOperation::Rename, temp_file))?;
+    }
+
+    ui_handler.finish();
+    Ok(EditSummary { changed: plan.changed })
+}
+
+/// 検証済みの編集内容
+struct EditPlan<'a> {
+    edit: &'a PackageEdit,
+    removed: HashSet<String>,
+    /// GUID → 新しいpathname
+    renamed: HashMap<String, String>,
+    changed: Vec<String>,
+    /// 進捗表示用のエントリ数
+    total: usize,
+}
+
+impl<'a> EditPlan<'a> {
+    fn new(entries: &[ListEntry], edit: &'a PackageEdit) -> Result<Self> {
+        let mut plan = EditPlan {
+            edit,
+            removed: HashSet::new(),
+            renamed: HashMap::new(),
+            changed: Vec::new(),
+            total: entries.len().max(1),
+        };
+
+        match edit {
+            PackageEdit::Remove { patterns } => {
+                let filter = PathFilter {
+                    include: patterns.clone(),
+                    exclude: Vec::new(),
+                };
+                for entry in entries {
+                    if let Some(pathname) = &entry.pathname
+                        && filter.matches(pathname)
+                    {
+                        plan.removed.insert(entry.guid.clone());
+                        plan.changed.push(pathname.clone());
+                    }
+                }
+                if plan.removed.is_empty() {
+                    return Err(Error::NoMatchingEntries { pattern: patterns.join(", ") });
+                }
+            }
+            PackageEdit::Move { from, to } => {
+                let from = from.trim_end_matches('/');
+                let to = to.trim_end_matches('/');
+                for entry in entries {
+                    let Some(pathname) = &entry.pathname else {
+                        continue;
+                    };
+                    let new_pathname = if pathname == from {
+                        to.to_string()
+                    } else if let Some(rest) = pathname.strip_prefix(from)
+                        && rest.starts_with('/')
+                    {
+                        format!("{}{}", to, rest)
+                    } else {
+                        continue;
+                    };
+                    plan.changed.push(format!("{} -> {}", pathname, new_pathname));
+                    plan.renamed.insert(entry.guid.clone(), new_pathname);
+                }
+                if plan.renamed.is_empty() {
+                    return Err(Error::NoMatchingEntries { pattern: from.to_string() });
+                }
+
+                // 移動先が出力先ディレクトリの外を指すpathnameにならないか確認する
+                let mut unsafe_entries: Vec<UnsafePathEntry> = plan
+                    .renamed
+                    .iter()
+                    .filter_map(|(guid, pathname)| {
+                        validate_pathname(pathname).err().map(|reason| UnsafePathEntry {
+                            guid: guid.clone(),
+                            pathname: pathname.clone(),
+                            reason,
+                        })
+                    })
+                    .collect();
+                if !unsafe_entries.is_empty() {
+                    unsafe_entries.sort_by(|a, b| a.pathname.cmp(&b.pathname));
+                    return Err(Error::UnsafePath { entries: unsafe_entries });
+                }
+
+                // 移動しないエントリと同じpathnameにならないか確認する
+                let existing: HashSet<&str> = entries
+                    .iter()
+                    .filter(|entry| !plan.renamed.contains_key(&entry.guid))
+                    .filter_map(|entry| entry.pathname.as_deref())
+                    .collect();
+                if let Some(pathname) = plan.renamed.values().find(|pathname| existing.contains(pathname.as_str())) {
+                    return Err(Error::PathnameCollision { pathname: pathname.clone() });
+                }
+            }
+            PackageEdit::Replace { guid, file } => {
+                let entry = entries
+                    .iter()
+                    .find(|entry| entry.guid == *guid)
+                    .ok_or_else(|| Error::GuidNotFound { guid: guid.clone() })?;
+                let pathname = entry.pathname.clone().unwrap_or_default();
+                if entry.entry_kind()? == EntryKind::Folder {
+                    return Err(Error::UnknownEntryKind {
+                        guid: guid.clone(),
+                        pathname,
+                        reason: "フォルダのassetは置き換えられません".to_string(),
+                    });
+                }
+                if !file.is_file() {
+                    return Err(Error::InputNotFound { path: file.clone() });
+                }
+                plan.changed.push(pathname);
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// 入力パッケージのファイルを順にコピーしながら編集内容を反映する
+    fn write<U: UiHandler>(&self, input_file: &Path, output_file: &Path, ui_handler: &mut U) -> Result<()> {
+        let file = File::open(input_file).map_err(Error::io(IoOperation::Open, input_file))?;
+        let mut archive = Archive::new(GzDecoder::new(BufReader::new(file)));
+        let mut writer = PackageWriter::create(output_file)?;
+        let mut seen: HashSet<String> = HashSet::new();
+
+        for entry in archive.entries().map_err(Error::archive(input_file))? {
+            if ui_handler.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+
+            let mut entry = entry.map_err(Error::archive(input_file))?;
+            let path = entry.path().map_err(Error::archive(input_file))?.to_path_buf();
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let Some((guid, file_name)) = split_archive_entry_path(&path) else {
+                continue;
+            };
+            if self.removed.contains(&guid) {
+                continue;
+            }
+            if seen.insert(guid.clone()) {
+                ui_handler.update_progress(seen.len() as f32 / self.total as f32, &guid);
+            }
+
+            match (self.edit, file_name.as_str()) {
+                (_, PATHNAME_FILENAME) if self.renamed.contains_key(&guid) => {
+                    writer.append_data(&guid, PATHNAME_FILENAME, self.renamed[&guid].as_bytes())?;
+                }
+                (PackageEdit::Replace { guid: target, file }, ASSET_FILE_NAME) if *target == guid => {
+                    let mut replacement = File::open(file).map_err(Error::io(IoOperation::Open, file))?;
+                    let size = replacement
+                        .metadata()
+                        .map_err(Error::io(IoOperation::Metadata, file))?
+                        .len();
+                    writer.append_reader(&guid, ASSET_FILE_NAME, size, &mut replacement)?;
+                }
+                _ => {
+                    let size = entry.size();
+                    writer.append_reader(&guid, &file_name, size, &mut entry)?;
+                }
+            }
+        }
+
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(guid: &str, pathname: &str) -> ListEntry {
+        ListEntry {
+            guid: guid.to_string(),
+            pathname: Some(pathname.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn entries() -> Vec<ListEntry> {
+        vec![
+            entry("a", "Assets/Foo"),
+            entry("b", "Assets/Foo/Bar.mat"),
+            entry("c", "Assets/FooBar.mat"),
+        ]
+    }
+
+    fn move_edit(from: &str, to: &str) -> PackageEdit {
+        PackageEdit::Move {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn move_renames_the_folder_and_its_contents() {
+        let entries = entries();
+        let edit = move_edit("Assets/Foo/", "Assets/Moved");
+        let plan = EditPlan::new(&entries, &edit).unwrap();
+        assert_eq!(plan.renamed.len(), 2);
+        assert_eq!(plan.renamed["a"], "Assets/Moved");
+        assert_eq!(plan.renamed["b"], "Assets/Moved/Bar.mat");
+    }
+
+    #[test]
+    fn move_rejects_unsafe_destinations() {
+        let entries = entries();
+        for to in ["../Outside", "/abs", "C:/Outside", "Assets/../..", ""] {
+            let edit = move_edit("Assets/Foo", to);
+            match EditPlan::new(&entries, &edit) {
+                Err(Error::UnsafePath { entries }) => assert_eq!(entries.len(), 2, "{}", to),
+                other => panic!("{}: {:?}", to, other.map(|plan| plan.changed)),
+            }
+        }
+    }
+
+    #[test]
+    fn move_rejects_collisions_with_remaining_entries() {
+        let entries = entries();
+        let edit = move_edit("Assets/Foo/Bar.mat", "Assets/FooBar.mat");
+        assert!(matches!(
+            EditPlan::new(&entries, &edit),
+            Err(Error::PathnameCollision { .. })
+        ));
+    }
+}
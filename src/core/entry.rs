@@ -0,0 +1,110 @@
+use crate::core::error::{Error, Result};
+use std::path::PathBuf;
+use yaml_rust::YamlLoader;
+
+/// エントリの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// フォルダ（assetを持たない）
+    Folder,
+    /// ファイル
+    File,
+}
+
+/// パッケージ内の1つのアセット
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageEntry {
+    pub guid: String,
+    pub pathname: String,
+    /// asset.metaの内容
+    pub meta: String,
+    pub kind: EntryKind,
+    /// 一時ディレクトリに展開したassetの場所（フォルダ、または展開していない場合はNone）
+    pub asset_location: Option<PathBuf>,
+    /// assetのサイズ（フォルダの場合はNone）
+    pub asset_size: Option<u64>,
+    /// preview.pngの内容（書き出し先が指定されている場合のみ読み込む）
+    pub preview: Option<Vec<u8>>,
+}
+
+/// 読み込み途中のエントリ
+/// アーカイブ内のファイルはGUIDごとに順不同で現れるため、すべて読み終えてから`build`する
+#[derive(Debug, Clone, Default)]
+pub struct EntryBuilder {
+    pub guid: String,
+    pub pathname: Option<String>,
+    pub meta: Option<String>,
+    pub asset_location: Option<PathBuf>,
+    pub asset_size: Option<u64>,
+    pub preview: Option<Vec<u8>>,
+}
+
+impl EntryBuilder {
+    pub fn new(guid: impl Into<String>) -> Self {
+        Self {
+            guid: guid.into(),
+            ..Default::default()
+        }
+    }
+
+    /// 必要な情報が揃っているか検証して`PackageEntry`を生成
+    pub fn build(self) -> Result<PackageEntry> {
+        let pathname = self
+            .pathname
+            .ok_or_else(|| Error::MissingPathname { guid: self.guid.clone() })?;
+        let meta = self.meta.ok_or_else(|| Error::MissingMeta {
+            guid: self.guid.clone(),
+            pathname: pathname.clone(),
+        })?;
+        let kind = entry_kind(&self.guid, &pathname, &meta, self.asset_size.is_some())?;
+
+        Ok(PackageEntry {
+            guid: self.guid,
+            pathname,
+            meta,
+            kind,
+            asset_location: self.asset_location,
+            asset_size: self.asset_size,
+            preview: self.preview,
+        })
+    }
+}
+
+/// metaの内容とassetの有無からエントリの種類を判定
+/// 両者が食い違う場合は推測せずにエラーとする
+pub fn entry_kind(guid: &str, pathname: &str, meta: &str, has_asset: bool) -> Result<EntryKind> {
+    match (is_folder_meta(pathname, meta)?, has_asset) {
+        (true, false) => Ok(EntryKind::Folder),
+        (false, true) => Ok(EntryKind::File),
+        (true, true) => Err(Error::UnknownEntryKind {
+            guid: guid.to_string(),
+            pathname: pathname.to_string(),
+            reason: "metaはフォルダを示していますがassetが含まれています".to_string(),
+        }),
+        (false, false) => Err(Error::UnknownEntryKind {
+            guid: guid.to_string(),
+            pathname: pathname.to_string(),
+            reason: "assetが含まれておらず、metaにfolderAsset: yesもありません".to_string(),
+        }),
+    }
+}
+
+/// metaファイルにfolderAsset: yesが含まれているか確認
+pub fn is_folder_meta(pathname: &str, meta: &str) -> Result<bool> {
+    let docs = YamlLoader::load_from_str(meta)
+        .map_err(|e| Error::MetaParse { path: pathname.to_string(), reason: e.to_string() })?;
+    let doc = docs
+        .first()
+        .ok_or_else(|| Error::MetaParse { path: pathname.to_string(), reason: "ルートが見つかりません".to_string() })?;
+    Ok(doc["folderAsset"].as_str() == Some("yes"))
+}
+
+/// metaファイルからインポーターの種類（`TextureImporter`など）を取得
+pub fn importer_type(meta: &str) -> Option<String> {
+    let docs = YamlLoader::load_from_str(meta).ok()?;
+    let hash = docs.first()?.as_hash()?;
+    hash.keys()
+        .filter_map(|key| key.as_str())
+        .find(|key| key.ends_with("Importer"))
+        .map(|key| key.to_string())
+}
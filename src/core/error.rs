@@ -0,0 +1,231 @@
+use crate::core::guid_index::GuidConflict;
+use crate::core::merge::MergeConflict;
+use crate::core::verify::VerifyIssue;
+use std::fmt;
+use std::path::PathBuf;
+
+/// 失敗したファイル操作の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoOperation {
+    Open,
+    Read,
+    Create,
+    Write,
+    CreateDir,
+    ReadDir,
+    Rename,
+    Remove,
+    Metadata,
+}
+
+impl fmt::Display for IoOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            IoOperation::Open => "ファイルのオープン",
+            IoOperation::Read => "ファイルの読み込み",
+            IoOperation::Create => "ファイルの作成",
+            IoOperation::Write => "ファイルの書き込み",
+            IoOperation::CreateDir => "ディレクトリの作成",
+            IoOperation::ReadDir => "ディレクトリの読み込み",
+            IoOperation::Rename => "ファイルの移動",
+            IoOperation::Remove => "ファイルの削除",
+            IoOperation::Metadata => "ファイル情報の取得",
+        };
+        f.write_str(text)
+    }
+}
+
+/// 安全でないpathnameを持つエントリ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsafePathEntry {
+    pub guid: String,
+    pub pathname: String,
+    pub reason: String,
+}
+
+/// 処理中に発生するエラー
+#[derive(Debug)]
+pub enum Error {
+    /// ファイル操作の失敗
+    Io {
+        operation: IoOperation,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// アーカイブ（tar.gz）として読み込めない、または構造が不正
+    MalformedArchive {
+        archive: PathBuf,
+        entry: Option<PathBuf>,
+        reason: String,
+    },
+    /// metaファイルのYAMLとして読み込めない
+    MetaParse { path: String, reason: String },
+    /// metaファイルにguidがない
+    MissingGuid { path: PathBuf },
+    /// GUIDフォルダにpathnameがない
+    MissingPathname { guid: String },
+    /// GUIDフォルダにasset.metaがない
+    MissingMeta { guid: String, pathname: String },
+    /// フォルダかファイルかを判定できない
+    UnknownEntryKind { guid: String, pathname: String, reason: String },
+    /// ファイルのassetが展開されていない
+    MissingAsset { guid: String, pathname: String },
+    /// 展開先プロジェクトの既存のアセットとGUIDが衝突する
+    GuidConflict { conflicts: Vec<GuidConflict> },
+    /// 結合するパッケージ間でGUIDまたはpathnameが衝突する
+    MergeConflict { conflicts: Vec<MergeConflict> },
+    /// パッケージに指定したGUIDのエントリがない
+    GuidNotFound { guid: String },
+    /// 指定したパターンにマッチするエントリがない
+    NoMatchingEntries { pattern: String },
+    /// 編集後のpathnameが既存のエントリと重複する
+    PathnameCollision { pathname: String },
+    /// パッケージの検証で問題が見つかった
+    VerifyFailed { issues: Vec<VerifyIssue> },
+    /// 出力先の外を指すpathnameがある
+    UnsafePath { entries: Vec<UnsafePathEntry> },
+    /// 入力ファイル・ディレクトリが存在しない
+    InputNotFound { path: PathBuf },
+    /// 入力がディレクトリではない
+    NotADirectory { path: PathBuf },
+    /// 圧縮対象のファイルがない
+    NoEntries { path: PathBuf },
+    /// 基準ディレクトリからの相対パスにできない
+    OutsideBaseDir { base: PathBuf, path: PathBuf },
+    /// ポリシーファイルの書式が不正
+    InvalidPolicy { path: PathBuf, line: usize, reason: String },
+    /// ユーザーによるキャンセル
+    Cancelled,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// ファイル操作のエラーを生成するクロージャを返す
+    /// `map_err(Error::io(IoOperation::Read, &path))` の形で使用する
+    pub fn io(operation: IoOperation, path: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> Error {
+        let path = path.into();
+        move |source| Error::Io {
+            operation,
+            path,
+            source,
+        }
+    }
+
+    /// アーカイブ読み込み時のエラーを生成するクロージャを返す
+    pub fn archive(archive: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> Error {
+        let archive = archive.into();
+        move |source| Error::MalformedArchive {
+            archive,
+            entry: None,
+            reason: source.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io {
+                operation,
+                path,
+                source,
+            } => write!(f, "{}に失敗しました: {}: {}", operation, path.display(), source),
+            Error::MalformedArchive {
+                archive,
+                entry: Some(entry),
+                reason,
+            } => write!(
+                f,
+                "アーカイブのエントリが不正です: {} ({}): {}",
+                archive.display(),
+                entry.display(),
+                reason
+            ),
+            Error::MalformedArchive {
+                archive,
+                entry: None,
+                reason,
+            } => write!(f, "アーカイブの読み込みに失敗しました: {}: {}", archive.display(), reason),
+            Error::MetaParse { path, reason } => {
+                write!(f, "{}のmetaファイルのパースに失敗しました: {}", path, reason)
+            }
+            Error::MissingGuid { path } => {
+                write!(f, "metaファイルにguidフィールドが見つかりません: {}", path.display())
+            }
+            Error::MissingPathname { guid } => write!(f, "pathnameが見つかりません: {}", guid),
+            Error::MissingMeta { guid, pathname } => {
+                write!(f, "asset.metaが見つかりません: {} ({})", pathname, guid)
+            }
+            Error::UnknownEntryKind { guid, pathname, reason } => {
+                write!(f, "エントリの種類を判定できません: {} ({}): {}", pathname, guid, reason)
+            }
+            Error::MissingAsset { guid, pathname } => {
+                write!(f, "assetが見つかりません: {} ({})", pathname, guid)
+            }
+            Error::GuidConflict { conflicts } => {
+                writeln!(f, "展開先プロジェクトの既存のアセットとGUIDが衝突するため中止しました:")?;
+                for conflict in conflicts {
+                    writeln!(f, "  {}", conflict)?;
+                }
+                write!(f, "--fail-on-guid-conflictを指定しない場合は警告のみで展開します")
+            }
+            Error::MergeConflict { conflicts } => {
+                writeln!(f, "結合するパッケージ間でエントリが衝突するため中止しました:")?;
+                for conflict in conflicts {
+                    writeln!(f, "  {}", conflict)?;
+                }
+                write!(f, "--on-conflict first|lastでどちらのエントリを残すか指定できます")
+            }
+            Error::GuidNotFound { guid } => write!(f, "パッケージにGUIDのエントリが見つかりません: {}", guid),
+            Error::NoMatchingEntries { pattern } => {
+                write!(f, "指定したパスにマッチするエントリが見つかりません: {}", pattern)
+            }
+            Error::PathnameCollision { pathname } => {
+                write!(f, "移動先のpathnameが既存のエントリと重複します: {}", pathname)
+            }
+            Error::VerifyFailed { issues } => {
+                writeln!(f, "パッケージの検証で{}件の問題が見つかりました:", issues.len())?;
+                for issue in issues {
+                    writeln!(f, "  {}", issue)?;
+                }
+                write!(f, "各エントリのpathname・asset.meta・assetを確認してください")
+            }
+            Error::UnsafePath { entries } => {
+                writeln!(f, "出力先の外を指す安全でないpathnameが含まれているため中止しました:")?;
+                for entry in entries {
+                    writeln!(f, "  {}: {:?}（{}）", entry.guid, entry.pathname, entry.reason)?;
+                }
+                write!(f, "信頼できるパッケージの場合は--allow-unsafe-pathsで展開できます")
+            }
+            Error::InputNotFound { path } => {
+                write!(f, "指定されたファイルが存在しません: {}", path.display())
+            }
+            Error::NotADirectory { path } => {
+                write!(f, "指定されたパスはディレクトリではありません: {}", path.display())
+            }
+            Error::NoEntries { path } => {
+                write!(f, "圧縮対象のファイルが見つかりませんでした: {}", path.display())
+            }
+            Error::OutsideBaseDir { base, path } => write!(
+                f,
+                "相対パスの取得に失敗しました: {}は{}の中にありません",
+                path.display(),
+                base.display()
+            ),
+            Error::InvalidPolicy { path, line, reason } => {
+                write!(f, "ポリシーファイルの{}行目が不正です: {}: {}", line, path.display(), reason)
+            }
+            Error::Cancelled => f.write_str("キャンセルされました"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
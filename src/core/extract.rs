@@ -1,109 +1,236 @@
+use crate::core::entry::{EntryBuilder, PackageEntry};
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::filter::PathFilter;
+use crate::core::path::{archive_entry_depth, split_archive_entry_path, PathRelocation};
+use crate::core::policy::OverwritePolicy;
 use crate::ui::UiHandler;
 use flate2::read::GzDecoder;
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use tar::Archive;
 
 pub const ASSET_FILE_NAME: &str = "asset";
 pub const ASSET_META_FILENAME: &str = "asset.meta";
 pub const PATHNAME_FILENAME: &str = "pathname";
+pub const PREVIEW_FILENAME: &str = "preview.png";
 
+/// 展開時のオプション
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractOptions {
+    /// 展開対象を絞り込むパスフィルタ（付け替え前のpathnameに適用）
+    pub filter: PathFilter,
+    /// pathnameの先頭の付け替え
+    pub relocation: PathRelocation,
+    /// 既存のファイルと内容が同じmeta・assetは上書きの確認をせずにそのまま残す
+    pub skip_identical: bool,
+    /// pathnameごとの上書きモードの規則（マッチしない場合は `UiHandler` の確認に従う）
+    pub overwrite_policy: OverwritePolicy,
+    /// 上書きする既存のmeta・assetを、出力先からの相対パスを保って移動しておくディレクトリ
+    pub backup_dir: Option<PathBuf>,
+    /// 一時ディレクトリを経由せず出力先に直接書き込む
+    pub direct: bool,
+    /// 出力先の外を指すpathnameを許可する
+    pub allow_unsafe_paths: bool,
+    /// プレビュー画像（preview.png）の書き出し先
+    pub export_previews: Option<PathBuf>,
+    /// 展開先の既存のアセットとGUIDが衝突する場合は書き込まずにエラーにする
+    pub fail_on_guid_conflict: bool,
+    /// 同じGUIDの既存のアセットが別のパスにある場合、パッケージのpathnameへ移動してから上書きする
+    pub upgrade: bool,
+    /// すべてのエントリに新しいGUIDを割り当て、テキスト形式のアセット内の参照も書き換える
+    pub remap_guids: bool,
+    /// リネームしたコピー同士の参照を、コピーに割り当てた新しいGUIDに書き換える
+    pub rewrite_renamed_references: bool,
+}
+
+/// 下位のリーダーから読み込んだバイト数を記録するリーダー
+/// 圧縮済みファイルの読み込み位置から進捗率を計算するために使用する
+pub struct ProgressReader<R> {
+    inner: R,
+    bytes_read: Rc<Cell<u64>>,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub fn new(inner: R) -> (Self, Rc<Cell<u64>>) {
+        let bytes_read = Rc::new(Cell::new(0));
+        (
+            Self {
+                inner,
+                bytes_read: Rc::clone(&bytes_read),
+            },
+            bytes_read,
+        )
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read.set(self.bytes_read.get() + n as u64);
+        Ok(n)
+    }
+}
+
+/// アーカイブを読み込み、assetを`output_dir/<GUID>`に書き出してエントリ一覧を返す
+/// エントリはpathname順に並ぶ
 pub fn extract_objects<U: UiHandler>(
     archive_path: &Path,
     output_dir: &Path,
-    objects: &mut HashMap<String, HashMap<String, String>>,
+    options: &ExtractOptions,
     ui_handler: &mut U,
-) -> Result<(), String> {
+) -> Result<Vec<PackageEntry>> {
+    let filter = &options.filter;
     if !output_dir.exists() {
-        std::fs::create_dir_all(output_dir).map_err(|e| format!("出力ディレクトリの作成に失敗しました: {}", e))?;
-    }
-
-    // まずエントリ数をカウント
-    let mut total = 0u32;
-    {
-        let file = File::open(archive_path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
-        let reader = BufReader::new(file);
-        let gz = GzDecoder::new(reader);
-        let mut archive = Archive::new(gz);
-
-        for entry in archive
-            .entries()
-            .map_err(|e| format!("アーカイブのエントリの取得に失敗しました: {}", e))?
-        {
-            entry.map_err(|e| format!("アーカイブのエントリの読み込みに失敗しました: {}", e))?;
-            total += 1;
-        }
+        std::fs::create_dir_all(output_dir).map_err(Error::io(IoOperation::CreateDir, output_dir))?;
     }
 
-
-    // 実際の処理用にアーカイブを再度開く
-    let file = File::open(archive_path).map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
-    let reader = BufReader::new(file);
-    let gz = GzDecoder::new(reader);
+    // 展開前のエントリ数のカウントは行わず、圧縮ファイルの読み込み量で進捗を計算する
+    let file = File::open(archive_path).map_err(Error::io(IoOperation::Open, archive_path))?;
+    let total = file
+        .metadata()
+        .map_err(Error::io(IoOperation::Metadata, archive_path))?
+        .len()
+        .max(1);
+    let (reader, bytes_read) = ProgressReader::new(file);
+    let gz = GzDecoder::new(BufReader::new(reader));
     let mut archive = Archive::new(gz);
 
-    let mut idx = 0u32;
+    let mut builders: HashMap<String, EntryBuilder> = HashMap::new();
+
     for entry in archive
         .entries()
-        .map_err(|e| format!("アーカイブのエントリの取得に失敗しました: {}", e))?
+        .map_err(Error::archive(archive_path))?
     {
         // キャンセルチェック
         if ui_handler.is_cancelled() {
-            return Err("キャンセルされました".to_string());
+            return Err(Error::Cancelled);
         }
 
-        idx += 1;
-        let mut entry = entry.map_err(|e| format!("アーカイブのエントリの読み込みに失敗しました: {}", e))?;
+        let mut entry = entry.map_err(Error::archive(archive_path))?;
         let path = entry
             .path()
-            .map_err(|e| format!("パスの取得に失敗しました: {}", e))?
+            .map_err(Error::archive(archive_path))?
             .to_path_buf();
 
         // UI更新
         ui_handler.update_progress(
-            (idx as f32) / (total as f32),
+            (bytes_read.get() as f32) / (total as f32),
             &path.display().to_string()
         );
 
-        if path.components().count() < 2 {
+        // GUIDフォルダなどのディレクトリのエントリは読み飛ばす
+        if !entry.header().entry_type().is_file() || archive_entry_depth(&path) < 2 {
             continue;
         }
-
-        let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
-        let folder = if let Some(parent) = path.parent() {
-            parent.to_str().unwrap().to_string()
-        } else {
-            "".to_string()
+        let Some((guid, file_name)) = split_archive_entry_path(&path) else {
+            return Err(Error::MalformedArchive {
+                archive: archive_path.to_path_buf(),
+                entry: Some(path),
+                reason: "エントリは<GUID>/<ファイル名>の形式である必要があります".to_string(),
+            });
         };
+        let builder = builders
+            .entry(guid.clone())
+            .or_insert_with(|| EntryBuilder::new(guid.clone()));
 
-        if file_name == ASSET_META_FILENAME || file_name == PATHNAME_FILENAME {
-            let mut string_entry = String::new();
-            entry
-                .read_to_string(&mut string_entry)
-                .map_err(|e| format!("ファイルの読み込みに失敗しました: {}", e))?;
+        match file_name.as_str() {
+            PATHNAME_FILENAME => {
+                let mut pathname = String::new();
+                entry
+                    .read_to_string(&mut pathname)
+                    .map_err(Error::archive(archive_path))?;
 
-            objects
-                .entry(folder)
-                .or_default()
-                .insert(file_name, string_entry);
-            continue;
-        }
-        if file_name != ASSET_FILE_NAME {
-            println!("unknown file: {}", file_name);
-            continue;
+                // assetがpathnameより先に書き出されていて、除外対象と分かった場合は削除する
+                if !filter.matches(&pathname)
+                    && let Some(location) = builder.asset_location.take()
+                {
+                    remove_extracted_asset(&location)?;
+                }
+                builder.pathname = Some(pathname);
+            }
+            ASSET_META_FILENAME => {
+                let mut meta = String::new();
+                entry
+                    .read_to_string(&mut meta)
+                    .map_err(Error::archive(archive_path))?;
+                builder.meta = Some(meta);
+            }
+            // プレビュー画像は書き出し先が指定されている場合のみ読み込む
+            PREVIEW_FILENAME => {
+                if options.export_previews.is_some() {
+                    let mut preview = Vec::new();
+                    entry
+                        .read_to_end(&mut preview)
+                        .map_err(Error::archive(archive_path))?;
+                    builder.preview = Some(preview);
+                }
+            }
+            ASSET_FILE_NAME => {
+                builder.asset_size = Some(entry.size());
+
+                // pathnameが既に分かっていて除外対象なら一時ディレクトリに書き出さない
+                if let Some(pathname) = &builder.pathname
+                    && !filter.matches(pathname)
+                {
+                    continue;
+                }
+
+                let out_path = output_dir.join(&guid);
+                let mut outfile = std::fs::File::create(&out_path)
+                    .map_err(Error::io(IoOperation::Create, &out_path))?;
+                std::io::copy(&mut entry, &mut outfile).map_err(Error::io(IoOperation::Write, &out_path))?;
+                builder.asset_location = Some(out_path);
+            }
+            _ => {
+                eprintln!("unknown file: {}", file_name);
+            }
         }
-        let out_path = output_dir.join(&folder);
-        if let Some(parent) = out_path.parent() {
-            if !parent.exists() {
-                std::fs::create_dir_all(parent).map_err(|e| format!("ディレクトリ作成失敗: {}", e))?;
+    }
+
+    // 祖先フォルダの判定には全pathnameが必要なため、最後に対象外のGUIDを取り除く
+    if !filter.is_empty() {
+        let pathnames: HashMap<String, String> = builders
+            .iter()
+            .filter_map(|(guid, builder)| {
+                builder
+                    .pathname
+                    .as_ref()
+                    .map(|pathname| (guid.clone(), pathname.clone()))
+            })
+            .collect();
+        let selected = filter.select(&pathnames);
+
+        let mut removed = Vec::new();
+        builders.retain(|guid, builder| {
+            // pathnameのないエントリは後でエラーとして報告するため残す
+            let keep = builder.pathname.is_none() || selected.contains(guid);
+            if !keep && let Some(location) = builder.asset_location.take() {
+                removed.push(location);
             }
+            keep
+        });
+        for location in removed {
+            remove_extracted_asset(&location)?;
         }
-        let mut outfile = std::fs::File::create(&out_path)
-            .map_err(|e| format!("ファイルの作成に失敗しました: {}", e))?;
-        std::io::copy(&mut entry, &mut outfile).map_err(|e| format!("ファイルの書き込みに失敗しました: {}", e))?;
     }
 
+    let mut entries = builders
+        .into_values()
+        .map(EntryBuilder::build)
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort_by(|a, b| a.pathname.cmp(&b.pathname).then_with(|| a.guid.cmp(&b.guid)));
+
+    Ok(entries)
+}
+
+/// 一時ディレクトリに書き出し済みのassetを削除
+fn remove_extracted_asset(path: &Path) -> Result<()> {
+    if path.is_file() {
+        std::fs::remove_file(path).map_err(Error::io(IoOperation::Remove, path))?;
+    }
     Ok(())
 }
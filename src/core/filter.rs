@@ -0,0 +1,180 @@
+use std::collections::{HashMap, HashSet};
+
+/// pathnameに対するinclude/excludeフィルタ
+///
+/// パターンは `/` 区切りのglobで、`*` は区切りを含まない任意の文字列、
+/// `**` は0個以上の階層、`?` は任意の1文字にマッチする。
+/// エントリ自身または祖先フォルダがパターンにマッチすればマッチとみなす。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl PathFilter {
+    /// フィルタが指定されていないか
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// 単一のpathnameが対象かどうか
+    pub fn matches(&self, pathname: &str) -> bool {
+        let included = self.include.is_empty()
+            || self.include.iter().any(|pattern| matches_self_or_ancestor(pattern, pathname));
+        let excluded = self.exclude.iter().any(|pattern| matches_self_or_ancestor(pattern, pathname));
+        included && !excluded
+    }
+
+    /// GUID→pathnameの対応から展開対象のGUIDを選択
+    /// 対象エントリの祖先フォルダもmetaを保持するために対象に含める
+    pub fn select(&self, pathnames: &HashMap<String, String>) -> HashSet<String> {
+        if self.is_empty() {
+            return pathnames.keys().cloned().collect();
+        }
+
+        let matched: Vec<&str> = pathnames
+            .values()
+            .filter(|pathname| self.matches(pathname))
+            .map(|pathname| pathname.as_str())
+            .collect();
+
+        let mut required: HashSet<&str> = HashSet::new();
+        for pathname in &matched {
+            required.insert(pathname);
+            let mut current = *pathname;
+            while let Some((parent, _)) = current.rsplit_once('/') {
+                required.insert(parent);
+                current = parent;
+            }
+        }
+
+        pathnames
+            .iter()
+            .filter(|(_, pathname)| required.contains(pathname.as_str()))
+            .map(|(guid, _)| guid.clone())
+            .collect()
+    }
+}
+
+/// pathname自身または祖先のいずれかがパターンにマッチするか
+pub(crate) fn matches_self_or_ancestor(pattern: &str, pathname: &str) -> bool {
+    let segments: Vec<&str> = pathname.split('/').filter(|s| !s.is_empty()).collect();
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    (1..=segments.len()).any(|len| match_segments(&pattern, &segments[..len]))
+}
+
+fn match_segments(pattern: &[&str], segments: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => (0..=segments.len()).any(|i| match_segments(rest, &segments[i..])),
+        Some((first, rest)) => match segments.split_first() {
+            Some((segment, remaining)) => {
+                match_segment(first, segment) && match_segments(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+/// 1階層分の名前を `*` と `?` を考慮して照合
+fn match_segment(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // 直前の `*` に1文字多くマッチさせて再試行
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    while p < pattern.len() && pattern[p] == '*' {
+        p += 1;
+    }
+    p == pattern.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        PathFilter {
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn match_segment_star_and_question() {
+        assert!(match_segment("*", "Foo.mat"));
+        assert!(match_segment("*.mat", "Foo.mat"));
+        assert!(match_segment("F*o*.mat", "Foo.mat"));
+        assert!(!match_segment("*.mat", "Foo.prefab"));
+        assert!(match_segment("Fo?.mat", "Foo.mat"));
+        assert!(!match_segment("Fo?.mat", "Fo.mat"));
+        assert!(match_segment("", ""));
+        assert!(!match_segment("", "Foo"));
+    }
+
+    #[test]
+    fn star_does_not_cross_folders() {
+        assert!(matches_self_or_ancestor("Assets/*.mat", "Assets/Foo.mat"));
+        assert!(!matches_self_or_ancestor("Assets/*.mat", "Assets/Sub/Foo.mat"));
+    }
+
+    #[test]
+    fn double_star_matches_any_depth() {
+        assert!(matches_self_or_ancestor("Assets/**/*.mat", "Assets/Foo.mat"));
+        assert!(matches_self_or_ancestor("Assets/**/*.mat", "Assets/A/B/Foo.mat"));
+        assert!(matches_self_or_ancestor("**/Editor", "Assets/Foo/Editor/Script.cs"));
+        assert!(matches_self_or_ancestor("**", "Assets/Foo"));
+        assert!(!matches_self_or_ancestor("Assets/**/*.mat", "Packages/Foo.mat"));
+    }
+
+    #[test]
+    fn patterns_are_anchored_at_the_root() {
+        assert!(matches_self_or_ancestor("Assets/Foo", "Assets/Foo"));
+        assert!(matches_self_or_ancestor("Assets/Foo", "Assets/Foo/Bar.txt"));
+        assert!(!matches_self_or_ancestor("Foo", "Assets/Foo"));
+        assert!(!matches_self_or_ancestor("Assets/Foo", "Assets/FooBar"));
+        assert!(matches_self_or_ancestor("**/Foo", "Assets/Foo"));
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let filter = filter(&["Assets/Foo/**"], &["**/Editor"]);
+        assert!(filter.matches("Assets/Foo/A.mat"));
+        assert!(!filter.matches("Assets/Foo/Editor/A.cs"));
+        assert!(!filter.matches("Assets/Bar/A.mat"));
+        assert!(PathFilter::default().matches("Anything"));
+    }
+
+    #[test]
+    fn select_keeps_ancestor_folders() {
+        let pathnames: HashMap<String, String> = [
+            ("1", "Assets"),
+            ("2", "Assets/Foo"),
+            ("3", "Assets/Foo/A.mat"),
+            ("4", "Assets/Bar"),
+        ]
+        .iter()
+        .map(|(guid, pathname)| (guid.to_string(), pathname.to_string()))
+        .collect();
+
+        let mut selected: Vec<String> = filter(&["**/*.mat"], &[]).select(&pathnames).into_iter().collect();
+        selected.sort();
+        assert_eq!(selected, ["1", "2", "3"]);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::core::guid_index::read_meta_guid;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// UnityのGUIDの長さ（16進数32桁）
+pub const GUID_LENGTH: usize = 32;
+
+/// Unity形式の新しいGUID（小文字の16進数32桁）を生成
+pub fn generate_guid() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    // RandomStateはプロセスごとにランダムな鍵を持つため、時刻と連番を混ぜて64bitずつ作る
+    let mut guid = String::with_capacity(GUID_LENGTH);
+    for half in 0..2u8 {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(nanos);
+        hasher.write_u64(count);
+        hasher.write_u32(std::process::id());
+        hasher.write_u8(half);
+        guid.push_str(&format!("{:016x}", hasher.finish()));
+    }
+    guid
+}
+
+/// Unity形式（小文字の16進数32桁）のGUIDかどうか
+pub fn is_valid_guid(guid: &str) -> bool {
+    guid.len() == GUID_LENGTH && guid.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// metaファイルの `guid:` 行を新しいGUIDに置き換える
+pub fn replace_meta_guid(meta: &str, new_guid: &str) -> String {
+    let Some(old_guid) = read_meta_guid(meta) else {
+        return meta.to_string();
+    };
+    let mut replaced = false;
+    meta.split_inclusive('\n')
+        .map(|line| {
+            if !replaced && line.starts_with("guid:") {
+                replaced = true;
+                line.replacen(old_guid, new_guid, 1)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+/// アセット内の `guid: <GUID>` 形式の参照を対応表に従って書き換える
+/// 書き換えがなかった場合は `None` を返す
+pub fn rewrite_guid_references(content: &str, mapping: &HashMap<String, String>) -> Option<String> {
+    const KEY: &str = "guid:";
+
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+    let mut changed = false;
+
+    while let Some(pos) = rest.find(KEY) {
+        let (before, after) = rest.split_at(pos + KEY.len());
+        result.push_str(before);
+
+        let value_start = after.len() - after.trim_start_matches(' ').len();
+        let value = &after[value_start..];
+        let guid_len = value
+            .bytes()
+            .take_while(|b| b.is_ascii_hexdigit())
+            .count();
+
+        if guid_len == GUID_LENGTH
+            && let Some(new_guid) = mapping.get(&value[..GUID_LENGTH])
+        {
+            result.push_str(&after[..value_start]);
+            result.push_str(new_guid);
+            rest = &value[GUID_LENGTH..];
+            changed = true;
+        } else {
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+
+    changed.then_some(result)
+}
+
+/// `--remap-guids` で参照を書き換えるテキスト形式のアセットの拡張子
+pub const TEXT_SERIALIZED_EXTENSIONS: &[&str] = &["prefab", "unity", "mat", "asset", "controller", "anim"];
+
+/// 参照を書き換える対象の拡張子か
+pub fn is_text_serialized(pathname: &str) -> bool {
+    pathname
+        .rsplit_once('.')
+        .is_some_and(|(_, extension)| TEXT_SERIALIZED_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// 展開するエントリすべてに新しいGUIDを割り当てる対応表
+#[derive(Debug, Clone, Default)]
+pub struct GuidRemap {
+    mapping: HashMap<String, String>,
+}
+
+impl GuidRemap {
+    /// 各GUIDに新しいGUIDを割り当てる
+    pub fn new<'a>(guids: impl IntoIterator<Item = &'a str>) -> Self {
+        let mapping = guids
+            .into_iter()
+            .map(|guid| (guid.to_string(), generate_guid()))
+            .collect();
+        Self { mapping }
+    }
+
+    /// metaの `guid:` 行と、インポート設定などに含まれる参照を新しいGUIDにする
+    pub fn remap_meta(&self, meta: &str) -> String {
+        rewrite_guid_references(meta, &self.mapping).unwrap_or_else(|| meta.to_string())
+    }
+
+    /// テキスト形式（`%YAML`）のアセット内の参照を新しいGUIDに書き換える
+    /// 対象外の拡張子、バイナリ形式のアセット、書き換えがない場合は `None` を返す
+    pub fn remap_asset(&self, pathname: &str, content: &[u8]) -> Option<Vec<u8>> {
+        if !is_text_serialized(pathname) {
+            return None;
+        }
+        let content = std::str::from_utf8(content).ok()?;
+        if !content.starts_with("%YAML") {
+            return None;
+        }
+        rewrite_guid_references(content, &self.mapping).map(String::into_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "0123456789abcdef0123456789abcdef";
+    const NEW: &str = "fedcba9876543210fedcba9876543210";
+    const OTHER: &str = "11111111111111111111111111111111";
+
+    fn mapping() -> HashMap<String, String> {
+        HashMap::from([(OLD.to_string(), NEW.to_string())])
+    }
+
+    #[test]
+    fn generate_guid_is_valid_and_unique() {
+        let a = generate_guid();
+        let b = generate_guid();
+        assert!(is_valid_guid(&a), "{}", a);
+        assert!(is_valid_guid(&b), "{}", b);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn rewrite_guid_references_rewrites_mapped_references() {
+        let content = format!(
+            "m_Material: {{fileID: 2100000, guid: {}, type: 2}}\nm_Shader: {{fileID: 4800000, guid: {}, type: 3}}\n",
+            OLD, OTHER
+        );
+        let expected = format!(
+            "m_Material: {{fileID: 2100000, guid: {}, type: 2}}\nm_Shader: {{fileID: 4800000, guid: {}, type: 3}}\n",
+            NEW, OTHER
+        );
+        assert_eq!(rewrite_guid_references(&content, &mapping()), Some(expected));
+    }
+
+    #[test]
+    fn rewrite_guid_references_rewrites_every_occurrence() {
+        let content = format!("guid: {}\n- guid:{}\n", OLD, OLD);
+        let expected = format!("guid: {}\n- guid:{}\n", NEW, NEW);
+        assert_eq!(rewrite_guid_references(&content, &mapping()), Some(expected));
+    }
+
+    #[test]
+    fn rewrite_guid_references_returns_none_without_changes() {
+        let content = format!("guid: {}\nname: guid:\n", OTHER);
+        assert_eq!(rewrite_guid_references(&content, &mapping()), None);
+        assert_eq!(rewrite_guid_references("", &mapping()), None);
+    }
+
+    #[test]
+    fn rewrite_guid_references_ignores_longer_hex_values() {
+        let content = format!("guid: {}0\n", OLD);
+        assert_eq!(rewrite_guid_references(&content, &mapping()), None);
+    }
+
+    #[test]
+    fn replace_meta_guid_replaces_only_the_guid_line() {
+        let meta = format!("fileFormatVersion: 2\nguid: {}\nuserData: {}\n", OLD, OLD);
+        let expected = format!("fileFormatVersion: 2\nguid: {}\nuserData: {}\n", NEW, OLD);
+        assert_eq!(replace_meta_guid(&meta, NEW), expected);
+    }
+
+    #[test]
+    fn is_text_serialized_checks_the_extension() {
+        assert!(is_text_serialized("Assets/A.prefab"));
+        assert!(is_text_serialized("Assets/A.MAT"));
+        assert!(!is_text_serialized("Assets/A.png"));
+        assert!(!is_text_serialized("Assets/prefab"));
+    }
+
+    #[test]
+    fn remap_meta_rewrites_the_guid_and_references() {
+        let remap = GuidRemap { mapping: mapping() };
+        let meta = format!(
+            "fileFormatVersion: 2\nguid: {}\nNativeFormatImporter:\n  externalObjects:\n    second: {{fileID: 2100000, guid: {}, type: 2}}\n  mainObject: {{fileID: 0, guid: {}, type: 3}}\n",
+            OLD, OLD, OTHER
+        );
+        let expected = format!(
+            "fileFormatVersion: 2\nguid: {}\nNativeFormatImporter:\n  externalObjects:\n    second: {{fileID: 2100000, guid: {}, type: 2}}\n  mainObject: {{fileID: 0, guid: {}, type: 3}}\n",
+            NEW, NEW, OTHER
+        );
+        assert_eq!(remap.remap_meta(&meta), expected);
+    }
+
+    #[test]
+    fn remap_meta_keeps_unmapped_metas() {
+        let remap = GuidRemap { mapping: mapping() };
+        let meta = format!("fileFormatVersion: 2\nguid: {}\n", OTHER);
+        assert_eq!(remap.remap_meta(&meta), meta);
+    }
+
+    #[test]
+    fn remap_asset_rewrites_only_text_serialized_yaml() {
+        let remap = GuidRemap { mapping: mapping() };
+        let yaml = format!("%YAML 1.1\nm_Script: {{fileID: 11500000, guid: {}, type: 3}}\n", OLD);
+        let expected = format!("%YAML 1.1\nm_Script: {{fileID: 11500000, guid: {}, type: 3}}\n", NEW);
+        assert_eq!(
+            remap.remap_asset("Assets/A.prefab", yaml.as_bytes()),
+            Some(expected.into_bytes())
+        );
+        assert_eq!(remap.remap_asset("Assets/A.txt", yaml.as_bytes()), None);
+        let binary = format!("\0binary guid: {}", OLD);
+        assert_eq!(remap.remap_asset("Assets/A.asset", binary.as_bytes()), None);
+    }
+
+    #[test]
+    fn guid_remap_assigns_new_valid_guids() {
+        let remap = GuidRemap::new([OLD, OTHER]);
+        let new_old = &remap.mapping[OLD];
+        let new_other = &remap.mapping[OTHER];
+        assert!(is_valid_guid(new_old) && is_valid_guid(new_other));
+        assert_ne!(new_old, OLD);
+        assert_ne!(new_old, new_other);
+        assert_eq!(remap.mapping.len(), 2);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::path::validate_pathname;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// 展開先プロジェクトの既存のmetaファイルから作ったGUIDとパスの対応表
+#[derive(Debug, Clone, Default)]
+pub struct GuidIndex {
+    /// GUID → pathname（同じGUIDが複数の場所にある場合はすべて保持）
+    by_guid: HashMap<String, Vec<String>>,
+    /// pathname → GUID
+    by_path: HashMap<String, String>,
+}
+
+/// GUIDの衝突の種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuidConflictKind {
+    /// 同じGUIDが別のパスに既に存在する
+    GuidAtOtherPath { existing_paths: Vec<String> },
+    /// 同じパスに別のGUIDのアセットが既に存在する
+    PathHasOtherGuid { existing_guid: String },
+}
+
+/// パッケージのエントリと展開先プロジェクトとのGUIDの衝突
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuidConflict {
+    pub guid: String,
+    pub pathname: String,
+    pub kind: GuidConflictKind,
+}
+
+impl fmt::Display for GuidConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            GuidConflictKind::GuidAtOtherPath { existing_paths } => write!(
+                f,
+                "{}: GUID {} は既に {} で使用されています",
+                self.pathname,
+                self.guid,
+                existing_paths.join(", ")
+            ),
+            GuidConflictKind::PathHasOtherGuid { existing_guid } => write!(
+                f,
+                "{}: 既存のアセットのGUIDは {} です（パッケージ: {}）",
+                self.pathname, existing_guid, self.guid
+            ),
+        }
+    }
+}
+
+impl GuidIndex {
+    /// `project_dir` 直下の `roots`（`Assets` など）以下にある*.metaを走査する
+    /// プロジェクト全体（Libraryなど）を走査しないよう、パッケージに含まれる最上位フォルダだけを対象にする
+    pub fn scan<'a>(project_dir: &Path, roots: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let mut index = GuidIndex::default();
+        let roots: BTreeSet<&str> = roots.into_iter().collect();
+        for root in roots {
+            let root_meta = project_dir.join(format!("{}.meta", root));
+            if root_meta.is_file() {
+                index.add_meta(&root_meta, root)?;
+            }
+            let root_dir = project_dir.join(root);
+            if root_dir.is_dir() {
+                index.scan_dir(&root_dir, root)?;
+            }
+        }
+        Ok(index)
+    }
+
+    /// パッケージのエントリのpathnameに含まれる最上位フォルダ以下を走査する
+    pub fn scan_for_pathnames<'a>(project_dir: &Path, pathnames: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let roots = pathnames
+            .into_iter()
+            .filter_map(|pathname| pathname.split('/').next())
+            // 出力先の外を走査しないよう、安全なフォルダ名だけを対象にする
+            .filter(|root| validate_pathname(root).is_ok());
+        Self::scan(project_dir, roots)
+    }
+
+    fn scan_dir(&mut self, dir: &Path, relative: &str) -> Result<()> {
+        let read_dir = std::fs::read_dir(dir).map_err(Error::io(IoOperation::ReadDir, dir))?;
+        for entry in read_dir {
+            let entry = entry.map_err(Error::io(IoOperation::ReadDir, dir))?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+
+            // Unityと同様に隠しファイル（一時ディレクトリを含む）は対象外
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            let path = entry.path();
+            let child = format!("{}/{}", relative, file_name);
+            if path.is_dir() {
+                self.scan_dir(&path, &child)?;
+            } else if let Some(asset_name) = child.strip_suffix(".meta") {
+                self.add_meta(&path, asset_name)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_meta(&mut self, meta_path: &Path, pathname: &str) -> Result<()> {
+        let content = std::fs::read_to_string(meta_path).map_err(Error::io(IoOperation::Read, meta_path))?;
+        if let Some(guid) = read_meta_guid(&content) {
+            self.insert(guid, pathname);
+        }
+        Ok(())
+    }
+
+    /// 対応を追加
+    pub fn insert(&mut self, guid: &str, pathname: &str) {
+        self.by_guid
+            .entry(guid.to_string())
+            .or_default()
+            .push(pathname.to_string());
+        self.by_path.insert(pathname.to_string(), guid.to_string());
+    }
+
+    /// GUIDが使われているパス
+    pub fn paths_for(&self, guid: &str) -> &[String] {
+        self.by_guid.get(guid).map(|paths| paths.as_slice()).unwrap_or(&[])
+    }
+
+    /// パスにあるアセットのGUID
+    pub fn guid_at(&self, pathname: &str) -> Option<&str> {
+        self.by_path.get(pathname).map(|guid| guid.as_str())
+    }
+
+    /// 索引に含まれるアセットの数
+    pub fn len(&self) -> usize {
+        self.by_path.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_path.is_empty()
+    }
+
+    /// パッケージのエントリ（GUID, pathname）と衝突するものを探す
+    pub fn find_conflicts<'a>(&self, entries: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<GuidConflict> {
+        let mut conflicts = Vec::new();
+        for (guid, pathname) in entries {
+            let existing_paths = self.paths_for(guid);
+            if !existing_paths.is_empty() && !existing_paths.iter().any(|path| path == pathname) {
+                conflicts.push(GuidConflict {
+                    guid: guid.to_string(),
+                    pathname: pathname.to_string(),
+                    kind: GuidConflictKind::GuidAtOtherPath {
+                        existing_paths: existing_paths.to_vec(),
+                    },
+                });
+            }
+            if let Some(existing_guid) = self.guid_at(pathname)
+                && existing_guid != guid
+            {
+                conflicts.push(GuidConflict {
+                    guid: guid.to_string(),
+                    pathname: pathname.to_string(),
+                    kind: GuidConflictKind::PathHasOtherGuid {
+                        existing_guid: existing_guid.to_string(),
+                    },
+                });
+            }
+        }
+        conflicts.sort_by(|a, b| a.pathname.cmp(&b.pathname));
+        conflicts
+    }
+}
+
+/// 展開先プロジェクトの既存のアセットとGUIDが衝突するエントリ（GUID, pathname）を探す
+pub fn find_project_conflicts(entries: &[(&str, &str)], project_dir: &Path) -> Result<Vec<GuidConflict>> {
+    let index = GuidIndex::scan_for_pathnames(project_dir, entries.iter().map(|(_, pathname)| *pathname))?;
+    Ok(index.find_conflicts(entries.iter().copied()))
+}
+
+/// metaファイルの `guid:` 行からGUIDを取得
+/// 数字のみのGUIDがYAMLで数値として読まれないよう、行単位で読み取る
+pub fn read_meta_guid(content: &str) -> Option<&str> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("guid:"))
+        .map(|guid| guid.trim())
+        .filter(|guid| !guid.is_empty())
+}
@@ -0,0 +1,261 @@
+use crate::core::entry::{entry_kind, importer_type, EntryKind};
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::filter::PathFilter;
+use crate::core::path::split_archive_entry_path;
+use crate::core::extract::{ASSET_FILE_NAME, ASSET_META_FILENAME, PATHNAME_FILENAME, PREVIEW_FILENAME};
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use tar::Archive;
+
+/// 一覧表示用のエントリ情報
+#[derive(Debug, Clone, Default)]
+pub struct ListEntry {
+    pub guid: String,
+    pub pathname: Option<String>,
+    pub asset_size: Option<u64>,
+    /// metaとassetの有無から判定した種類（pathnameやmetaがない、または両者が食い違う場合はNone）
+    pub kind: Option<EntryKind>,
+    /// preview.pngを含むか
+    pub has_preview: bool,
+    /// metaに記載されたインポーターの種類
+    pub importer: Option<String>,
+    /// assetのSHA-256（16進数）
+    pub sha256: Option<String>,
+    /// asset.metaの内容
+    pub meta: Option<String>,
+}
+
+impl ListEntry {
+    /// エントリの種類を判定する。判定できない場合は展開時と同じエラーを返す
+    pub fn entry_kind(&self) -> Result<EntryKind> {
+        let pathname = self
+            .pathname
+            .as_deref()
+            .ok_or_else(|| Error::MissingPathname { guid: self.guid.clone() })?;
+        let meta = self.meta.as_deref().ok_or_else(|| Error::MissingMeta {
+            guid: self.guid.clone(),
+            pathname: pathname.to_string(),
+        })?;
+        entry_kind(&self.guid, pathname, meta, self.asset_size.is_some())
+    }
+
+    /// 一覧表示用の種類の名前（判定できない場合は `unknown`）
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            Some(EntryKind::Folder) => "folder",
+            Some(EntryKind::File) => "file",
+            None => "unknown",
+        }
+    }
+}
+
+/// パッケージを展開せずにエントリ一覧を取得
+/// assetはディスクに書き出さず、サイズとハッシュのみを記録する
+pub fn list_entries(archive_path: &Path) -> Result<Vec<ListEntry>> {
+    scan_entries(archive_path, true)
+}
+
+/// エントリ一覧を取得し、`hash_assets` が有効な場合だけassetのハッシュを計算する（無効な場合 `sha256` は常にNone）
+/// GUIDの衝突の確認など、pathnameとmetaだけが必要な場合はハッシュの計算を省く
+pub(crate) fn scan_entries(archive_path: &Path, hash_assets: bool) -> Result<Vec<ListEntry>> {
+    let file = File::open(archive_path).map_err(Error::io(IoOperation::Open, archive_path))?;
+    let reader = BufReader::new(file);
+    let gz = GzDecoder::new(reader);
+    let mut archive = Archive::new(gz);
+
+    let mut entries: HashMap<String, ListEntry> = HashMap::new();
+
+    for entry in archive
+        .entries()
+        .map_err(Error::archive(archive_path))?
+    {
+        let mut entry = entry.map_err(Error::archive(archive_path))?;
+        let path = entry
+            .path()
+            .map_err(Error::archive(archive_path))?
+            .to_path_buf();
+
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let Some((guid, file_name)) = split_archive_entry_path(&path) else {
+            continue;
+        };
+
+        let list_entry = entries.entry(guid.clone()).or_insert_with(|| ListEntry {
+            guid: guid.clone(),
+            ..Default::default()
+        });
+
+        match file_name.as_str() {
+            PATHNAME_FILENAME => {
+                let mut pathname = String::new();
+                entry
+                    .read_to_string(&mut pathname)
+                    .map_err(Error::archive(archive_path))?;
+                list_entry.pathname = Some(pathname);
+            }
+            ASSET_META_FILENAME => {
+                let mut meta = String::new();
+                entry
+                    .read_to_string(&mut meta)
+                    .map_err(Error::archive(archive_path))?;
+                list_entry.meta = Some(meta);
+            }
+            ASSET_FILE_NAME => {
+                list_entry.asset_size = Some(entry.size());
+                if !hash_assets {
+                    continue;
+                }
+                let mut hasher = Sha256::new();
+                std::io::copy(&mut entry, &mut hasher).map_err(Error::archive(archive_path))?;
+                list_entry.sha256 = Some(hex_string(&hasher.finalize()));
+            }
+            PREVIEW_FILENAME => {
+                list_entry.has_preview = true;
+            }
+            _ => {}
+        }
+    }
+
+    // 一覧表示では種類を判定できないエントリがあっても中断せず、kindをNoneとして返す
+    for list_entry in entries.values_mut() {
+        list_entry.kind = list_entry.entry_kind().ok();
+        list_entry.importer = list_entry.meta.as_deref().and_then(importer_type);
+    }
+
+    let mut entries: Vec<ListEntry> = entries.into_values().collect();
+    entries.sort_by(|a, b| a.pathname.cmp(&b.pathname).then_with(|| a.guid.cmp(&b.guid)));
+
+    Ok(entries)
+}
+
+/// フィルタで選択されるエントリだけを残す（展開時と同様に親フォルダも残る）
+pub fn select_entries(entries: Vec<ListEntry>, filter: &PathFilter) -> Vec<ListEntry> {
+    if filter.is_empty() {
+        return entries;
+    }
+    let pathnames: HashMap<String, String> = entries
+        .iter()
+        .filter_map(|entry| entry.pathname.clone().map(|pathname| (entry.guid.clone(), pathname)))
+        .collect();
+    let selected: HashSet<String> = filter.select(&pathnames);
+    entries
+        .into_iter()
+        .filter(|entry| selected.contains(&entry.guid))
+        .collect()
+}
+
+/// ファイルの内容のSHA-256（`ListEntry::sha256` と同じ形式）
+pub(crate) fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path).map_err(Error::io(IoOperation::Open, path))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(Error::io(IoOperation::Read, path))?;
+    Ok(hex_string(&hasher.finalize()))
+}
+
+/// バイト列を小文字の16進数文字列にする
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{meta, temp_dir, write_package};
+    use crate::core::writer::PackageWriter;
+
+    #[test]
+    fn entry_kinds_come_from_meta_and_asset() {
+        let dir = temp_dir("list-kinds");
+        let package = dir.join("package.unitypackage");
+        let mut writer = PackageWriter::create(&package).unwrap();
+        // assetがなくfolderAsset: yesもないエントリはフォルダとして扱わない
+        let guid = "00000000000000000000000000000003";
+        writer.append_data(guid, PATHNAME_FILENAME, b"Assets/Broken").unwrap();
+        writer.append_data(guid, ASSET_META_FILENAME, meta(guid).as_bytes()).unwrap();
+        writer.finish().unwrap();
+        let broken = list_entries(&package).unwrap().remove(0);
+        assert_eq!(broken.kind, None);
+        assert_eq!(broken.kind_name(), "unknown");
+        assert!(matches!(broken.entry_kind(), Err(Error::UnknownEntryKind { .. })));
+
+        write_package(
+            &package,
+            &[
+                ("00000000000000000000000000000001", "Assets/Foo", None),
+                ("00000000000000000000000000000002", "Assets/Foo/a.txt", Some(b"a")),
+            ],
+        );
+        let entries = list_entries(&package).unwrap();
+        assert_eq!(entries[0].kind, Some(EntryKind::Folder));
+        assert_eq!(entries[1].kind, Some(EntryKind::File));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// フォルダ1つとファイル2つ（一方はプレビュー付き）のパッケージ
+    fn write_small_package(package: &Path) {
+        let mut writer = PackageWriter::create(package).unwrap();
+        let folder = "00000000000000000000000000000001";
+        writer.append_data(folder, PATHNAME_FILENAME, b"Assets/Foo").unwrap();
+        let folder_meta = format!("{}folderAsset: yes\n", meta(folder));
+        writer.append_data(folder, ASSET_META_FILENAME, folder_meta.as_bytes()).unwrap();
+        for (guid, pathname, asset, preview) in [
+            ("00000000000000000000000000000002", "Assets/Foo/a.txt", &b"a"[..], true),
+            ("00000000000000000000000000000003", "Assets/Foo/b.txt", &b"bcd"[..], false),
+        ] {
+            writer.append_data(guid, PATHNAME_FILENAME, pathname.as_bytes()).unwrap();
+            let meta = format!("{}TextScriptImporter:\n  userData:\n", meta(guid));
+            writer.append_data(guid, ASSET_META_FILENAME, meta.as_bytes()).unwrap();
+            writer.append_data(guid, ASSET_FILE_NAME, asset).unwrap();
+            if preview {
+                writer.append_data(guid, PREVIEW_FILENAME, b"png").unwrap();
+            }
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn list_entries_reports_sizes_previews_and_hashes() {
+        let dir = temp_dir("list-entries");
+        let package = dir.join("package.unitypackage");
+        write_small_package(&package);
+
+        let entries = list_entries(&package).unwrap();
+        let pathnames: Vec<Option<&str>> = entries.iter().map(|entry| entry.pathname.as_deref()).collect();
+        assert_eq!(pathnames, [Some("Assets/Foo"), Some("Assets/Foo/a.txt"), Some("Assets/Foo/b.txt")]);
+        let sizes: Vec<Option<u64>> = entries.iter().map(|entry| entry.asset_size).collect();
+        assert_eq!(sizes, [None, Some(1), Some(3)]);
+        assert_eq!(entries.iter().filter(|entry| entry.has_preview).count(), 1);
+        assert!(entries[1].has_preview);
+        assert_eq!(entries[1].importer.as_deref(), Some("TextScriptImporter"));
+        assert_eq!(
+            entries[1].sha256.as_deref(),
+            Some("ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb")
+        );
+        assert_eq!(entries[0].sha256, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scan_entries_skips_hashing_when_not_requested() {
+        let dir = temp_dir("list-scan");
+        let package = dir.join("package.unitypackage");
+        write_small_package(&package);
+
+        let entries = scan_entries(&package, false).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|entry| entry.sha256.is_none()));
+        // ハッシュを省いてもサイズ・プレビュー・種類は同じように取得する
+        let sizes: Vec<Option<u64>> = entries.iter().map(|entry| entry.asset_size).collect();
+        assert_eq!(sizes, [None, Some(1), Some(3)]);
+        assert_eq!(entries.iter().filter(|entry| entry.has_preview).count(), 1);
+        let kinds: Vec<&str> = entries.iter().map(|entry| entry.kind_name()).collect();
+        assert_eq!(kinds, ["folder", "file", "file"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
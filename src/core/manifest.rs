@@ -0,0 +1,246 @@
+use crate::core::entry::EntryKind;
+use crate::core::list::ListEntry;
+use std::fmt::Write;
+
+/// 機械可読なマニフェストの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// エントリごとのオブジェクトを並べたJSON配列
+    Json,
+    /// ヘッダー付きのCSV
+    Csv,
+    /// pathnameの階層をインデントで表したツリー
+    Tree,
+}
+
+impl ManifestFormat {
+    /// コマンドライン引数の値から変換
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(ManifestFormat::Json),
+            "csv" => Some(ManifestFormat::Csv),
+            "tree" => Some(ManifestFormat::Tree),
+            _ => None,
+        }
+    }
+}
+
+/// エントリ一覧を指定の形式で文字列にする
+/// 差分を取りやすいよう、エントリはpathname順に出力する
+pub fn render_manifest(entries: &[ListEntry], format: ManifestFormat) -> String {
+    match format {
+        ManifestFormat::Json => render_json(entries),
+        ManifestFormat::Csv => render_csv(entries),
+        ManifestFormat::Tree => render_tree(entries),
+    }
+}
+
+fn render_json(entries: &[ListEntry]) -> String {
+    let mut out = String::from("[\n");
+    for (idx, entry) in entries.iter().enumerate() {
+        let fields = [
+            ("guid", json_string(&entry.guid)),
+            ("pathname", json_optional_string(entry.pathname.as_deref())),
+            ("kind", json_string(entry.kind_name())),
+            (
+                "size",
+                entry.asset_size.map(|size| size.to_string()).unwrap_or_else(|| "null".to_string()),
+            ),
+            ("importer", json_optional_string(entry.importer.as_deref())),
+            ("sha256", json_optional_string(entry.sha256.as_deref())),
+        ];
+        let body = fields
+            .iter()
+            .map(|(key, value)| format!("\"{}\": {}", key, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let separator = if idx + 1 < entries.len() { "," } else { "" };
+        let _ = writeln!(out, "  {{{}}}{}", body, separator);
+    }
+    out.push_str("]\n");
+    out
+}
+
+pub(crate) fn json_optional_string(value: Option<&str>) -> String {
+    value.map(json_string).unwrap_or_else(|| "null".to_string())
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn render_csv(entries: &[ListEntry]) -> String {
+    let mut out = String::from("guid,pathname,kind,size,importer,sha256\n");
+    for entry in entries {
+        let fields = [
+            csv_field(&entry.guid),
+            csv_field(entry.pathname.as_deref().unwrap_or("")),
+            entry.kind_name().to_string(),
+            entry.asset_size.map(|size| size.to_string()).unwrap_or_default(),
+            csv_field(entry.importer.as_deref().unwrap_or("")),
+            entry.sha256.clone().unwrap_or_default(),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_tree(entries: &[ListEntry]) -> String {
+    // 文字列順では "Foo-bar" が "Foo/" の間に入るため、階層ごとに比較して並べる
+    let mut sorted: Vec<&ListEntry> = entries.iter().collect();
+    sorted.sort_by(|a, b| {
+        let a_parts = a.pathname.as_deref().map(|p| p.split('/').collect::<Vec<_>>());
+        let b_parts = b.pathname.as_deref().map(|p| p.split('/').collect::<Vec<_>>());
+        a_parts.cmp(&b_parts).then_with(|| a.guid.cmp(&b.guid))
+    });
+
+    let mut out = String::new();
+    let mut printed: Vec<&str> = Vec::new();
+    for entry in sorted {
+        let Some(pathname) = entry.pathname.as_deref() else {
+            let _ = writeln!(out, "(pathnameなし) [{}]", entry.guid);
+            continue;
+        };
+        let parts: Vec<&str> = pathname.split('/').collect();
+        let (name, ancestors) = parts.split_last().unwrap();
+
+        // パッケージに含まれない中間フォルダも階層が分かるように出力する
+        let common = printed
+            .iter()
+            .zip(ancestors.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        printed.truncate(common);
+        for ancestor in &ancestors[common..] {
+            let _ = writeln!(out, "{}{}/", "  ".repeat(printed.len()), ancestor);
+            printed.push(ancestor);
+        }
+
+        let indent = "  ".repeat(printed.len());
+        match entry.kind {
+            Some(EntryKind::Folder) => {
+                let _ = writeln!(out, "{}{}/ [{}]", indent, name, entry.guid);
+                printed.push(name);
+            }
+            None => {
+                let _ = writeln!(out, "{}{} [{}] unknown", indent, name, entry.guid);
+            }
+            Some(EntryKind::File) => {
+                let _ = writeln!(
+                    out,
+                    "{}{} [{}] {} bytes, {}, sha256:{}",
+                    indent,
+                    name,
+                    entry.guid,
+                    entry.asset_size.unwrap_or(0),
+                    entry.importer.as_deref().unwrap_or("-"),
+                    entry.sha256.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(guid: &str, pathname: &str) -> ListEntry {
+        ListEntry {
+            guid: guid.to_string(),
+            pathname: Some(pathname.to_string()),
+            asset_size: Some(3),
+            kind: Some(EntryKind::File),
+            importer: Some("DefaultImporter".to_string()),
+            sha256: Some("abc".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn folder(guid: &str, pathname: &str) -> ListEntry {
+        ListEntry {
+            guid: guid.to_string(),
+            pathname: Some(pathname.to_string()),
+            kind: Some(EntryKind::Folder),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn csv_quotes_commas_and_quotes() {
+        let entries = [
+            file("01", "Assets/a,b.txt"),
+            file("02", "Assets/say \"hi\".txt"),
+            file("03", "Assets/plain.txt"),
+        ];
+
+        assert_eq!(
+            render_manifest(&entries, ManifestFormat::Csv),
+            "guid,pathname,kind,size,importer,sha256\n\
+             01,\"Assets/a,b.txt\",file,3,DefaultImporter,abc\n\
+             02,\"Assets/say \"\"hi\"\".txt\",file,3,DefaultImporter,abc\n\
+             03,Assets/plain.txt,file,3,DefaultImporter,abc\n"
+        );
+    }
+
+    #[test]
+    fn json_escapes_strings_and_writes_null_for_missing_values() {
+        let entries = [file("01", "Assets/\"q\"\\\t.txt"), folder("02", "Assets")];
+
+        assert_eq!(
+            render_manifest(&entries, ManifestFormat::Json),
+            "[\n  {\"guid\": \"01\", \"pathname\": \"Assets/\\\"q\\\"\\\\\\t.txt\", \"kind\": \"file\", \"size\": 3, \
+             \"importer\": \"DefaultImporter\", \"sha256\": \"abc\"},\n  \
+             {\"guid\": \"02\", \"pathname\": \"Assets\", \"kind\": \"folder\", \"size\": null, \
+             \"importer\": null, \"sha256\": null}\n]\n"
+        );
+        assert_eq!(json_string("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn tree_nests_entries_under_their_folders() {
+        let entries = [
+            file("04", "Assets/Foo-bar.txt"),
+            file("03", "Assets/Foo/Sub/b.txt"),
+            file("02", "Assets/Foo/a.txt"),
+            folder("01", "Assets/Foo"),
+        ];
+
+        // Assetsと Assets/Foo/Sub はパッケージに含まれない中間フォルダとして出力される
+        assert_eq!(
+            render_manifest(&entries, ManifestFormat::Tree),
+            "Assets/\n\
+             \x20 Foo/ [01]\n\
+             \x20   Sub/\n\
+             \x20     b.txt [03] 3 bytes, DefaultImporter, sha256:abc\n\
+             \x20   a.txt [02] 3 bytes, DefaultImporter, sha256:abc\n\
+             \x20 Foo-bar.txt [04] 3 bytes, DefaultImporter, sha256:abc\n"
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::list::{list_entries, ListEntry};
+use crate::core::path::split_archive_entry_path;
+use crate::core::writer::PackageWriter;
+use crate::ui::UiHandler;
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use tar::Archive;
+
+/// 結合時に衝突した場合の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    /// 衝突があれば何も書き込まずにエラーにする
+    #[default]
+    Error,
+    /// 先に指定したパッケージのエントリを残す
+    First,
+    /// 後に指定したパッケージのエントリで置き換える
+    Last,
+}
+
+impl MergePolicy {
+    /// コマンドライン引数の値から変換
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "error" => Some(MergePolicy::Error),
+            "first" => Some(MergePolicy::First),
+            "last" => Some(MergePolicy::Last),
+            _ => None,
+        }
+    }
+}
+
+/// 結合時の衝突の種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeConflictKind {
+    /// 同じGUIDで内容またはpathnameが異なるエントリがある
+    Guid { other_pathname: Option<String> },
+    /// 同じpathnameに別のGUIDのエントリがある
+    Pathname { other_guid: String },
+}
+
+/// 結合するパッケージ間の衝突
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    pub guid: String,
+    pub pathname: Option<String>,
+    /// 後から現れたエントリのパッケージ
+    pub package: PathBuf,
+    /// 先に現れたエントリのパッケージ
+    pub other_package: PathBuf,
+    pub kind: MergeConflictKind,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pathname = self.pathname.as_deref().unwrap_or("(pathnameなし)");
+        match &self.kind {
+            MergeConflictKind::Guid { other_pathname } => write!(
+                f,
+                "{} [{}]: {} の {} と同じGUIDで内容が異なります（{}）",
+                pathname,
+                self.guid,
+                self.other_package.display(),
+                other_pathname.as_deref().unwrap_or("(pathnameなし)"),
+                self.package.display()
+            ),
+            MergeConflictKind::Pathname { other_guid } => write!(
+                f,
+                "{} [{}]: {} に同じpathnameのエントリ [{}] があります（{}）",
+                pathname,
+                self.guid,
+                self.other_package.display(),
+                other_guid,
+                self.package.display()
+            ),
+        }
+    }
+}
+
+/// 複数のパッケージのエントリを1つのパッケージにまとめる
+///
+/// プロジェクトに展開せず、各パッケージのGUIDフォルダをそのままコピーするため、
+/// metaのないエントリやプレビュー画像も失われない。すべて同じエントリは1つにまとめる。
+/// `policy` が `MergePolicy::Error` の場合は衝突があると出力ファイルを作成せずにエラーにする。
+/// それ以外の場合は解決した衝突の一覧を返す。
+pub fn merge_packages<U: UiHandler>(
+    inputs: &[PathBuf],
+    output_file: &Path,
+    policy: MergePolicy,
+    ui_handler: &mut U,
+) -> Result<Vec<MergeConflict>> {
+    ui_handler.update_progress(0.0, "エントリを確認しています");
+
+    // GUIDごとに採用するパッケージを決める
+    let mut chosen: HashMap<String, (usize, ListEntry)> = HashMap::new();
+    let mut guid_by_pathname: HashMap<String, String> = HashMap::new();
+    let mut conflicts = Vec::new();
+
+    for (package_idx, input) in inputs.iter().enumerate() {
+        for entry in list_entries(input)? {
+            let mut found = Vec::new();
+            if let Some((other_idx, other)) = chosen.get(&entry.guid) {
+                if same_entry(other, &entry) {
+                    continue;
+                }
+                found.push((
+                    *other_idx,
+                    other.guid.clone(),
+                    MergeConflictKind::Guid {
+                        other_pathname: other.pathname.clone(),
+                    },
+                ));
+            }
+            if let Some(pathname) = &entry.pathname
+                && let Some(other_guid) = guid_by_pathname.get(pathname)
+                && *other_guid != entry.guid
+            {
+                let other_idx = chosen[other_guid].0;
+                found.push((
+                    other_idx,
+                    other_guid.clone(),
+                    MergeConflictKind::Pathname {
+                        other_guid: other_guid.clone(),
+                    },
+                ));
+            }
+
+            for (other_idx, _, kind) in &found {
+                conflicts.push(MergeConflict {
+                    guid: entry.guid.clone(),
+                    pathname: entry.pathname.clone(),
+                    package: input.clone(),
+                    other_package: inputs[*other_idx].clone(),
+                    kind: kind.clone(),
+                });
+            }
+
+            if !found.is_empty() && policy != MergePolicy::Last {
+                continue;
+            }
+
+            // 後のパッケージを優先する場合は衝突した既存のエントリを取り除く
+            for (_, other_guid, _) in found {
+                if let Some((_, other)) = chosen.remove(&other_guid)
+                    && let Some(other_pathname) = other.pathname
+                    && guid_by_pathname.get(&other_pathname) == Some(&other_guid)
+                {
+                    guid_by_pathname.remove(&other_pathname);
+                }
+            }
+            if let Some(pathname) = &entry.pathname {
+                guid_by_pathname.insert(pathname.clone(), entry.guid.clone());
+            }
+            chosen.insert(entry.guid.clone(), (package_idx, entry));
+        }
+    }
+
+    if policy == MergePolicy::Error && !conflicts.is_empty() {
+        return Err(Error::MergeConflict { conflicts });
+    }
+
+    // 出力ファイルが入力のいずれかと同じ場合に読み込む前に壊さないよう、一時ファイルに書き込んでから移動する
+    let mut temp_file = output_file.as_os_str().to_os_string();
+    temp_file.push(".tmp");
+    let temp_file = PathBuf::from(temp_file);
+    if let Err(e) = write_chosen(inputs, &temp_file, &chosen, ui_handler) {
+        if temp_file.exists() {
+            let _ = std::fs::remove_file(&temp_file);
+        }
+        return Err(e);
+    }
+    std::fs::rename(&temp_file, output_file).map_err(Error::io(IoOperation::Rename, &temp_file))?;
+
+    ui_handler.finish();
+    Ok(conflicts)
+}
+
+/// 採用したエントリのファイルを各パッケージからそのままコピーする
+fn write_chosen<U: UiHandler>(
+    inputs: &[PathBuf],
+    output_file: &Path,
+    chosen: &HashMap<String, (usize, ListEntry)>,
+    ui_handler: &mut U,
+) -> Result<()> {
+    let mut writer = PackageWriter::create(output_file)?;
+    let total = inputs.len() as f32;
+    for (package_idx, input) in inputs.iter().enumerate() {
+        if ui_handler.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        ui_handler.update_progress((package_idx + 1) as f32 / total, &input.display().to_string());
+
+        let file = File::open(input).map_err(Error::io(IoOperation::Open, input))?;
+        let mut archive = Archive::new(GzDecoder::new(BufReader::new(file)));
+        for entry in archive.entries().map_err(Error::archive(input))? {
+            let mut entry = entry.map_err(Error::archive(input))?;
+            let path = entry.path().map_err(Error::archive(input))?.to_path_buf();
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let Some((guid, file_name)) = split_archive_entry_path(&path) else {
+                continue;
+            };
+            if chosen.get(&guid).is_some_and(|(idx, _)| *idx == package_idx) {
+                let size = entry.size();
+                writer.append_reader(&guid, &file_name, size, &mut entry)?;
+            }
+        }
+    }
+    writer.finish()
+}
+
+/// pathname・asset・metaがすべて同じエントリか
+fn same_entry(a: &ListEntry, b: &ListEntry) -> bool {
+    a.pathname == b.pathname && a.sha256 == b.sha256 && a.meta == b.meta && a.has_preview == b.has_preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{temp_dir, write_package};
+    use crate::ui::{OverwriteMode, SilentHandler};
+
+    const GUID_A: &str = "0123456789abcdef0123456789abcdef";
+    const GUID_B: &str = "fedcba9876543210fedcba9876543210";
+
+    fn merge(inputs: &[PathBuf], output_file: &Path, policy: MergePolicy) -> Result<Vec<MergeConflict>> {
+        merge_packages(inputs, output_file, policy, &mut SilentHandler::new(OverwriteMode::Skip))
+    }
+
+    /// `(GUID, pathname, assetのサイズ)` の一覧
+    fn entries_of(path: &Path) -> Vec<(String, Option<String>, Option<u64>)> {
+        let mut entries: Vec<_> = list_entries(path)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.guid, entry.pathname, entry.asset_size))
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    fn entry(guid: &str, pathname: &str, size: u64) -> (String, Option<String>, Option<u64>) {
+        (guid.to_string(), Some(pathname.to_string()), Some(size))
+    }
+
+    #[test]
+    fn merge_combines_packages_and_deduplicates_identical_entries() {
+        let dir = temp_dir("merge-combine");
+        let a = dir.join("a.unitypackage");
+        let b = dir.join("b.unitypackage");
+        write_package(&a, &[(GUID_A, "Assets/A.txt", Some(b"a"))]);
+        write_package(&b, &[(GUID_A, "Assets/A.txt", Some(b"a")), (GUID_B, "Assets/B.txt", Some(b"bb"))]);
+
+        let output = dir.join("out.unitypackage");
+        let conflicts = merge(&[a, b], &output, MergePolicy::Error).unwrap();
+
+        assert_eq!(conflicts, []);
+        assert_eq!(
+            entries_of(&output),
+            [entry(GUID_A, "Assets/A.txt", 1), entry(GUID_B, "Assets/B.txt", 2)]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn merge_reports_guid_conflicts_without_writing() {
+        let dir = temp_dir("merge-guid-conflict");
+        let a = dir.join("a.unitypackage");
+        let b = dir.join("b.unitypackage");
+        write_package(&a, &[(GUID_A, "Assets/A.txt", Some(b"a"))]);
+        write_package(&b, &[(GUID_A, "Assets/A.txt", Some(b"changed"))]);
+
+        let output = dir.join("out.unitypackage");
+        let result = merge(&[a.clone(), b.clone()], &output, MergePolicy::Error);
+
+        let Err(Error::MergeConflict { conflicts }) = result else {
+            panic!("{:?}", result);
+        };
+        assert_eq!(
+            conflicts,
+            [MergeConflict {
+                guid: GUID_A.to_string(),
+                pathname: Some("Assets/A.txt".to_string()),
+                package: b,
+                other_package: a,
+                kind: MergeConflictKind::Guid {
+                    other_pathname: Some("Assets/A.txt".to_string()),
+                },
+            }]
+        );
+        assert!(!output.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn merge_reports_pathname_conflicts() {
+        let dir = temp_dir("merge-pathname-conflict");
+        let a = dir.join("a.unitypackage");
+        let b = dir.join("b.unitypackage");
+        write_package(&a, &[(GUID_A, "Assets/A.txt", Some(b"a"))]);
+        write_package(&b, &[(GUID_B, "Assets/A.txt", Some(b"bb"))]);
+
+        let output = dir.join("out.unitypackage");
+        let result = merge(&[a, b], &output, MergePolicy::Error);
+
+        let Err(Error::MergeConflict { conflicts }) = result else {
+            panic!("{:?}", result);
+        };
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].guid, GUID_B);
+        assert_eq!(
+            conflicts[0].kind,
+            MergeConflictKind::Pathname {
+                other_guid: GUID_A.to_string()
+            }
+        );
+        assert!(!output.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn merge_resolves_conflicts_by_policy() {
+        let dir = temp_dir("merge-policy");
+        let a = dir.join("a.unitypackage");
+        let b = dir.join("b.unitypackage");
+        write_package(&a, &[(GUID_A, "Assets/A.txt", Some(b"a"))]);
+        write_package(
+            &b,
+            &[(GUID_A, "Assets/A.txt", Some(b"changed")), (GUID_B, "Assets/A.txt", Some(b"bb"))],
+        );
+        let inputs = [a, b];
+
+        let first = dir.join("first.unitypackage");
+        let conflicts = merge(&inputs, &first, MergePolicy::First).unwrap();
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(entries_of(&first), [entry(GUID_A, "Assets/A.txt", 1)]);
+
+        // 後のパッケージを優先する場合、同じpathnameの別のGUIDのエントリがさらに置き換える
+        let last = dir.join("last.unitypackage");
+        let conflicts = merge(&inputs, &last, MergePolicy::Last).unwrap();
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(entries_of(&last), [entry(GUID_B, "Assets/A.txt", 2)]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn merge_can_overwrite_an_input_package() {
+        let dir = temp_dir("merge-in-place");
+        let a = dir.join("a.unitypackage");
+        let b = dir.join("b.unitypackage");
+        write_package(&a, &[(GUID_A, "Assets/A.txt", Some(b"a"))]);
+        write_package(&b, &[(GUID_B, "Assets/B.txt", Some(b"bb"))]);
+
+        merge(&[a.clone(), b], &a, MergePolicy::Error).unwrap();
+
+        assert_eq!(
+            entries_of(&a),
+            [entry(GUID_A, "Assets/A.txt", 1), entry(GUID_B, "Assets/B.txt", 2)]
+        );
+        assert!(!dir.join("a.unitypackage.tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
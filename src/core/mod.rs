@@ -1,7 +1,27 @@
+pub mod entry;
 pub mod extract;
+pub mod filter;
+pub mod guid;
+pub mod guid_index;
 pub mod rebuild;
+pub mod backup;
 pub mod compress;
+pub mod diff;
+pub mod edit;
+pub mod direct;
+pub mod error;
+pub mod list;
+pub mod manifest;
+pub mod merge;
+pub mod path;
+pub mod plan;
+pub mod policy;
+pub mod preview;
+pub mod reader;
+pub mod uninstall;
+pub mod upgrade;
+pub mod verify;
+pub mod writer;
 
-pub use extract::extract_objects;
-pub use rebuild::rebuild_objects;
-pub use compress::compress_directory;
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,262 @@
+use crate::core::error::{Error, UnsafePathEntry};
+use std::path::{Component, Path};
+
+/// Windowsで予約されているデバイス名
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 展開時にpathnameの先頭を付け替える設定
+///
+/// `strip_prefix`、`strip_components`、`prefix` の順に適用する。
+/// UnityはGUIDで参照するため、付け替えても参照は壊れない。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathRelocation {
+    /// 取り除く先頭のフォルダ（`Assets/Vendor` など）
+    pub strip_prefix: Option<String>,
+    /// 取り除く先頭のパス要素の数
+    pub strip_components: usize,
+    /// 先頭に付け加えるフォルダ（`Assets/ThirdParty` など）
+    pub prefix: Option<String>,
+}
+
+impl PathRelocation {
+    /// 付け替えが指定されていないか
+    pub fn is_empty(&self) -> bool {
+        self.strip_prefix.is_none() && self.strip_components == 0 && self.prefix.is_none()
+    }
+
+    /// 付け替え後のpathname
+    /// `strip_prefix` の外にあるエントリや、取り除いた結果空になるエントリは `None`（展開しない）
+    pub fn apply(&self, pathname: &str) -> Option<String> {
+        let mut rest = pathname;
+        if let Some(strip_prefix) = &self.strip_prefix {
+            let strip_prefix = strip_prefix.trim_end_matches('/');
+            rest = if rest == strip_prefix {
+                ""
+            } else {
+                rest.strip_prefix(strip_prefix)?.strip_prefix('/')?
+            };
+        }
+        let rest = rest.splitn(self.strip_components + 1, '/').nth(self.strip_components)?;
+        if rest.is_empty() {
+            return None;
+        }
+
+        match self.prefix.as_deref().map(|prefix| prefix.trim_end_matches('/')) {
+            Some(prefix) if !prefix.is_empty() => Some(format!("{}/{}", prefix, rest)),
+            _ => Some(rest.to_string()),
+        }
+    }
+}
+
+/// pathnameが出力先ディレクトリの外を指していないか検証
+/// 問題がある場合はその理由を返す
+pub fn validate_pathname(pathname: &str) -> Result<(), String> {
+    if pathname.is_empty() {
+        return Err("空のパスです".to_string());
+    }
+    if pathname.contains('\0') {
+        return Err("NUL文字が含まれています".to_string());
+    }
+    if pathname.starts_with('/') || pathname.starts_with('\\') {
+        return Err("絶対パスです".to_string());
+    }
+    if pathname.len() >= 2 && pathname.as_bytes()[1] == b':' {
+        return Err("ドライブ指定付きのパスです".to_string());
+    }
+
+    for component in pathname.split(['/', '\\']) {
+        match component {
+            "" => return Err("空のパス要素が含まれています".to_string()),
+            "." | ".." => return Err(format!("'{}'が含まれています", component)),
+            _ => {}
+        }
+        if component.contains(':') {
+            return Err("':'が含まれています".to_string());
+        }
+        let stem = component.split('.').next().unwrap_or("").to_ascii_uppercase();
+        if RESERVED_NAMES.contains(&stem.as_str()) {
+            return Err(format!("予約されたデバイス名'{}'が含まれています", component));
+        }
+    }
+
+    Ok(())
+}
+
+/// すべてのpathnameを検証し、問題のあるエントリを一覧にしたエラーを返す
+pub fn check_pathnames<'a, I>(pathnames: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    let mut entries: Vec<UnsafePathEntry> = pathnames
+        .into_iter()
+        .filter_map(|(guid, pathname)| {
+            validate_pathname(pathname).err().map(|reason| UnsafePathEntry {
+                guid: guid.to_string(),
+                pathname: pathname.to_string(),
+                reason,
+            })
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    entries.sort_by(|a, b| a.guid.cmp(&b.guid));
+    Err(Error::UnsafePath { entries })
+}
+
+/// アーカイブ内のエントリパスの、`./` などを除いたパス要素の数
+pub fn archive_entry_depth(path: &Path) -> usize {
+    path.components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .count()
+}
+
+/// `<GUID>/<ファイル名>` の形式のエントリパスをGUIDとファイル名に分ける
+/// 先頭の `./` は無視する。形式が異なる場合（`..` や絶対パスを含む場合も）は `None`
+pub fn split_archive_entry_path(path: &Path) -> Option<(String, String)> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            _ => return None,
+        }
+    }
+    let [guid, file_name] = <[String; 2]>::try_from(parts).ok()?;
+    Some((guid, file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_archive_entry_path_ignores_leading_cur_dir() {
+        let expected = Some(("0123".to_string(), "asset".to_string()));
+        assert_eq!(split_archive_entry_path(Path::new("0123/asset")), expected);
+        assert_eq!(split_archive_entry_path(Path::new("./0123/asset")), expected);
+        assert_eq!(archive_entry_depth(Path::new("./0123/")), 1);
+        assert_eq!(archive_entry_depth(Path::new("./")), 0);
+    }
+
+    #[test]
+    fn split_archive_entry_path_rejects_other_shapes() {
+        assert_eq!(split_archive_entry_path(Path::new("0123")), None);
+        assert_eq!(split_archive_entry_path(Path::new("0123/sub/asset")), None);
+        assert_eq!(split_archive_entry_path(Path::new("../0123/asset")), None);
+        assert_eq!(split_archive_entry_path(Path::new("/0123/asset")), None);
+    }
+
+    #[test]
+    fn validate_pathname_accepts_relative_paths() {
+        assert_eq!(validate_pathname("Assets"), Ok(()));
+        assert_eq!(validate_pathname("Assets/Foo/Bar.mat"), Ok(()));
+        assert_eq!(validate_pathname("Assets/..hidden/a.b.c"), Ok(()));
+        assert_eq!(validate_pathname("Assets/Console.cs"), Ok(()));
+    }
+
+    #[test]
+    fn validate_pathname_rejects_parent_and_current_dir() {
+        assert!(validate_pathname("../escaped.txt").is_err());
+        assert!(validate_pathname("Assets/../../escaped.txt").is_err());
+        assert!(validate_pathname("Assets\\..\\escaped.txt").is_err());
+        assert!(validate_pathname("./Assets").is_err());
+        assert!(validate_pathname("Assets//Foo").is_err());
+        assert!(validate_pathname("").is_err());
+    }
+
+    #[test]
+    fn validate_pathname_rejects_absolute_paths() {
+        assert!(validate_pathname("/etc/passwd").is_err());
+        assert!(validate_pathname("\\\\server\\share").is_err());
+        assert!(validate_pathname("C:\\Windows\\evil.dll").is_err());
+        assert!(validate_pathname("c:/evil.txt").is_err());
+        assert!(validate_pathname("Assets/file:stream").is_err());
+    }
+
+    #[test]
+    fn validate_pathname_rejects_nul_and_reserved_names() {
+        assert!(validate_pathname("Assets/a\0b").is_err());
+        assert!(validate_pathname("Assets/CON").is_err());
+        assert!(validate_pathname("Assets/nul.txt").is_err());
+        assert!(validate_pathname("Assets/Com1.meta").is_err());
+        assert!(validate_pathname("LPT9/a.txt").is_err());
+    }
+
+    #[test]
+    fn check_pathnames_lists_every_unsafe_entry() {
+        let result = check_pathnames([("b", "/abs"), ("ok", "Assets/ok"), ("a", "../up")]);
+        let Err(Error::UnsafePath { entries }) = result else {
+            panic!("UnsafePathになっていません: {:?}", result);
+        };
+        let guids: Vec<&str> = entries.iter().map(|entry| entry.guid.as_str()).collect();
+        assert_eq!(guids, ["a", "b"]);
+        assert!(check_pathnames([("ok", "Assets/ok")]).is_ok());
+    }
+
+    fn relocation(strip_prefix: Option<&str>, strip_components: usize, prefix: Option<&str>) -> PathRelocation {
+        PathRelocation {
+            strip_prefix: strip_prefix.map(str::to_string),
+            strip_components,
+            prefix: prefix.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn relocation_strip_prefix_matches_whole_segments() {
+        let relocation = relocation(Some("Assets/Foo/"), 0, None);
+        assert_eq!(relocation.apply("Assets/Foo/Bar.mat"), Some("Bar.mat".to_string()));
+        assert_eq!(relocation.apply("Assets/Foo/Sub/Baz.png"), Some("Sub/Baz.png".to_string()));
+        assert_eq!(relocation.apply("Assets/FooBar"), None);
+        assert_eq!(relocation.apply("Assets/FooBar/Baz.mat"), None);
+        assert_eq!(relocation.apply("Assets/Other.mat"), None);
+        // 取り除いた結果空になるフォルダ自身は展開しない
+        assert_eq!(relocation.apply("Assets/Foo"), None);
+    }
+
+    #[test]
+    fn relocation_strip_components() {
+        let relocation = relocation(None, 2, None);
+        assert_eq!(relocation.apply("Assets/Foo/Bar/Baz.mat"), Some("Bar/Baz.mat".to_string()));
+        assert_eq!(relocation.apply("Assets/Foo/Bar.mat"), Some("Bar.mat".to_string()));
+        assert_eq!(relocation.apply("Assets/Foo"), None);
+        assert_eq!(relocation.apply("Assets"), None);
+    }
+
+    #[test]
+    fn relocation_adds_prefix() {
+        let relocation = relocation(None, 0, Some("Assets/ThirdParty/"));
+        assert_eq!(relocation.apply("Foo/Bar.mat"), Some("Assets/ThirdParty/Foo/Bar.mat".to_string()));
+    }
+
+    #[test]
+    fn relocation_combines_strip_and_prefix() {
+        let relocation = relocation(Some("Assets/Vendor"), 1, Some("Assets/ThirdParty"));
+        assert_eq!(
+            relocation.apply("Assets/Vendor/Foo/Bar.mat"),
+            Some("Assets/ThirdParty/Bar.mat".to_string())
+        );
+        assert_eq!(relocation.apply("Assets/Vendor/Foo"), None);
+        assert_eq!(relocation.apply("Assets/VendorFoo/Bar.mat"), None);
+    }
+
+    #[test]
+    fn relocation_without_options_keeps_pathnames() {
+        let relocation = PathRelocation::default();
+        assert!(relocation.is_empty());
+        assert_eq!(relocation.apply("Assets/Foo/Bar.mat"), Some("Assets/Foo/Bar.mat".to_string()));
+
+        // 空の先頭フォルダは付け加えない
+        let relocation = PathRelocation {
+            prefix: Some(String::new()),
+            ..Default::default()
+        };
+        assert!(!relocation.is_empty());
+        assert_eq!(relocation.apply("Assets/Foo"), Some("Assets/Foo".to_string()));
+    }
+}
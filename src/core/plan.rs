@@ -0,0 +1,463 @@
+use crate::core::entry::EntryKind;
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::extract::ExtractOptions;
+use crate::core::guid::GuidRemap;
+use crate::core::list::{file_sha256, ListEntry};
+use crate::core::rebuild::{place_file, Placement, PlacementCheck};
+use crate::core::upgrade::{path_before_upgrade, UpgradeMove};
+use crate::ui::{OverwriteAction, OverwriteMode, OverwriteModes, OverwriteTarget};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// ドライランで判定した処理内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlannedAction {
+    /// 新規作成
+    Create,
+    /// 既存のファイルを上書き
+    Overwrite,
+    /// 既存のファイルがあるためスキップ
+    Skip,
+    /// 既存のファイルと衝突するため別名で作成（出力先ディレクトリからの相対パス）
+    Rename { to: String },
+    /// 毎回確認するモードのため、実行時の応答で決まる
+    Ask,
+    /// 既存のフォルダ・metaをそのまま使用
+    Keep,
+    /// 既存のファイルと内容が同じためそのまま使用
+    Identical,
+}
+
+/// ドライランで判定した1ファイル分の処理
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedOperation {
+    pub guid: String,
+    /// 出力先ディレクトリからの相対パス
+    pub path: String,
+    pub is_folder: bool,
+    pub action: PlannedAction,
+}
+
+/// ディスクに書き込まずに、展開時に各ファイルがどう扱われるかを判定する
+///
+/// ファイルごとの判定は `rebuild::handle_file` と同じ `place_file` で行い、
+/// 先に作成される予定のファイルも既存として扱う。`entries` のpathnameは付け替え・検証済みのものを渡す。
+/// `upgrade_moves` には展開前に移動する既存のアセットを渡し、移動後の状態で判定する。
+/// metaとassetはそれぞれ `overwrite_modes` の対応するモードで判定し、
+/// `options.overwrite_policy` の規則にマッチしたpathnameは規則のモードで判定する。
+/// `options.skip_identical` が有効な場合は、既存のファイルと内容が同じmeta・assetを `Identical` とする
+/// （assetの比較には `ListEntry::sha256` を使う）。
+pub fn plan_extraction(
+    entries: &[ListEntry],
+    output_dir: &Path,
+    overwrite_modes: OverwriteModes,
+    upgrade_moves: &[UpgradeMove],
+    options: &ExtractOptions,
+) -> Result<Vec<PlannedOperation>> {
+    // 展開時と同じく、metaは新しいGUIDに書き換えたものと比較する
+    let remap = options
+        .remap_guids
+        .then(|| GuidRemap::new(entries.iter().map(|entry| entry.guid.as_str())));
+
+    let mut planner = Planner {
+        output_dir,
+        overwrite_modes,
+        upgrade_moves,
+        options,
+        remap: remap.as_ref(),
+        planned: HashSet::new(),
+        operations: Vec::new(),
+    };
+    for entry in entries {
+        let Some(pathname) = entry.pathname.as_deref() else {
+            continue;
+        };
+        match entry.entry_kind()? {
+            EntryKind::Folder => planner.plan_directory(&entry.guid, pathname),
+            EntryKind::File => planner.plan_file(entry, pathname)?,
+        }
+    }
+
+    Ok(planner.operations)
+}
+
+struct Planner<'a> {
+    output_dir: &'a Path,
+    overwrite_modes: OverwriteModes,
+    upgrade_moves: &'a [UpgradeMove],
+    options: &'a ExtractOptions,
+    remap: Option<&'a GuidRemap>,
+    /// このドライランで作成される予定のパス
+    planned: HashSet<PathBuf>,
+    operations: Vec<PlannedOperation>,
+}
+
+impl Planner<'_> {
+    fn exists(&self, path: &Path) -> bool {
+        if self.planned.contains(path) {
+            return true;
+        }
+        match path_before_upgrade(self.output_dir, path, self.upgrade_moves) {
+            Some(moved_from) => moved_from.exists(),
+            None => path.exists(),
+        }
+    }
+
+    /// 比較する既存のファイル（予定した移動を反映したパス）
+    /// 比較しない場合（このドライランで作成される予定のファイルを含む）はNone
+    fn comparable(&self, path: &Path) -> Option<PathBuf> {
+        if !self.options.skip_identical || self.planned.contains(path) {
+            return None;
+        }
+        let current = path_before_upgrade(self.output_dir, path, self.upgrade_moves).unwrap_or_else(|| path.to_path_buf());
+        current.is_file().then_some(current)
+    }
+
+    fn push(&mut self, guid: &str, path: String, is_folder: bool, action: PlannedAction) {
+        self.operations.push(PlannedOperation {
+            guid: guid.to_string(),
+            path,
+            is_folder,
+            action,
+        });
+    }
+
+    fn plan_directory(&mut self, guid: &str, pathname: &str) {
+        let dir_path = self.output_dir.join(pathname);
+        let action = if self.exists(&dir_path) { PlannedAction::Keep } else { PlannedAction::Create };
+        self.push(guid, pathname.to_string(), true, action);
+        self.planned.insert(dir_path);
+
+        // 既存のフォルダのmetaは上書きしない
+        let meta_path = self.output_dir.join(format!("{}.meta", pathname));
+        let action = if self.exists(&meta_path) { PlannedAction::Keep } else { PlannedAction::Create };
+        self.push(guid, format!("{}.meta", pathname), false, action);
+        self.planned.insert(meta_path);
+    }
+
+    fn plan_file(&mut self, entry: &ListEntry, pathname: &str) -> Result<()> {
+        let guid = entry.guid.as_str();
+        let (parent_rel, file_name) = match pathname.rsplit_once('/') {
+            Some((parent, name)) => (Some(parent), name),
+            None => (None, pathname),
+        };
+        let relative = |name: &str| match parent_rel {
+            Some(parent) => format!("{}/{}", parent, name),
+            None => name.to_string(),
+        };
+        let parent_dir = match parent_rel {
+            Some(parent) => self.output_dir.join(parent),
+            None => self.output_dir.to_path_buf(),
+        };
+
+        let meta = entry.meta.as_deref().unwrap_or_default();
+        let mut check = PlanCheck {
+            planner: self,
+            entry,
+            pathname,
+            meta: match self.remap {
+                Some(remap) => remap.remap_meta(meta),
+                None => meta.to_string(),
+            },
+            overwrite_modes: match self.options.overwrite_policy.mode_for(pathname) {
+                Some(mode) => OverwriteModes::from(mode),
+                None => self.overwrite_modes,
+            },
+        };
+        let placement = place_file(&mut check, &parent_dir, pathname)?;
+
+        let meta_name = format!("{}.meta", file_name);
+        match &placement.meta {
+            Placement::Create => {
+                self.planned.insert(parent_dir.join(&meta_name));
+            }
+            Placement::Rename { to } => {
+                self.planned.insert(parent_dir.join(format!("{}.meta", to)));
+            }
+            _ => {}
+        }
+        let action = planned_action(&placement.meta, |to| relative(&format!("{}.meta", to)));
+        self.push(guid, relative(&meta_name), false, action);
+
+        let asset_name = placement.asset_name.as_str();
+        match &placement.asset {
+            Placement::Create => {
+                // metaをリネームした場合はassetも同じ名前で作成される
+                let action = if asset_name == file_name {
+                    PlannedAction::Create
+                } else {
+                    PlannedAction::Rename { to: relative(asset_name) }
+                };
+                self.push(guid, relative(file_name), false, action);
+                self.planned.insert(parent_dir.join(asset_name));
+            }
+            Placement::Identical => {
+                self.push(guid, relative(file_name), false, PlannedAction::Identical);
+            }
+            Placement::Rename { to } => {
+                self.push(guid, relative(asset_name), false, PlannedAction::Rename { to: relative(to) });
+                self.planned.insert(parent_dir.join(to));
+                self.planned.insert(parent_dir.join(format!("{}.meta", to)));
+            }
+            placement => {
+                self.push(guid, relative(asset_name), false, planned_action(placement, relative));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 判定結果をドライランの処理にする（リネーム先のファイル名は `relative` で出力先からの相対パスにする）
+fn planned_action(placement: &Placement, relative: impl FnOnce(&str) -> String) -> PlannedAction {
+    match placement {
+        Placement::Create => PlannedAction::Create,
+        Placement::Identical => PlannedAction::Identical,
+        Placement::Overwrite => PlannedAction::Overwrite,
+        Placement::Skip => PlannedAction::Skip,
+        Placement::Ask => PlannedAction::Ask,
+        Placement::Rename { to } => PlannedAction::Rename { to: relative(to) },
+    }
+}
+
+/// ドライランの確認（作成予定のパスと上書きモードで判定し、UIには確認しない）
+struct PlanCheck<'p, 'a> {
+    planner: &'p Planner<'a>,
+    entry: &'p ListEntry,
+    pathname: &'p str,
+    /// 書き込む予定のmeta（`--remap-guids` の場合は書き換え後）
+    meta: String,
+    overwrite_modes: OverwriteModes,
+}
+
+impl PlacementCheck for PlanCheck<'_, '_> {
+    fn exists(&self, path: &Path) -> bool {
+        self.planner.exists(path)
+    }
+
+    fn meta_identical(&mut self, path: &Path) -> Result<bool> {
+        let Some(current) = self.planner.comparable(path) else {
+            return Ok(false);
+        };
+        let content = std::fs::read_to_string(&current).map_err(Error::io(IoOperation::Read, &current))?;
+        Ok(content == self.meta)
+    }
+
+    fn asset_identical(&mut self, path: &Path) -> Result<bool> {
+        let Some(current) = self.planner.comparable(path) else {
+            return Ok(false);
+        };
+        let size = std::fs::metadata(&current).map_err(Error::io(IoOperation::Metadata, &current))?.len();
+        if self.entry.asset_size != Some(size) || self.entry.sha256 != Some(file_sha256(&current)?) {
+            return Ok(false);
+        }
+        // パッケージのassetと既存のファイルは同じ内容のため、既存のファイルで書き換えの有無を確認する
+        match self.planner.remap {
+            Some(remap) => {
+                let content = std::fs::read(&current).map_err(Error::io(IoOperation::Read, &current))?;
+                Ok(remap.remap_asset(self.pathname, &content).is_none())
+            }
+            None => Ok(true),
+        }
+    }
+
+    fn resolve(&mut self, _path: &str, target: OverwriteTarget) -> Option<OverwriteAction> {
+        match self.overwrite_modes.for_target(target) {
+            OverwriteMode::Overwrite => Some(OverwriteAction::Overwrite),
+            OverwriteMode::Rename => Some(OverwriteAction::Rename),
+            OverwriteMode::Skip => Some(OverwriteAction::Skip),
+            OverwriteMode::Ask => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::reader::PackageReader;
+    use crate::core::testing::{meta, temp_dir, write_package};
+    use crate::ui::SilentHandler;
+
+    const GUID: &str = "0123456789abcdef0123456789abcdef";
+
+    fn actions(operations: &[PlannedOperation]) -> Vec<(&str, &PlannedAction)> {
+        operations
+            .iter()
+            .map(|operation| (operation.path.as_str(), &operation.action))
+            .collect()
+    }
+
+    fn rename(to: &str) -> PlannedAction {
+        PlannedAction::Rename { to: to.to_string() }
+    }
+
+    /// `Assets/a.txt` だけを含むパッケージと、既存のファイルを置いたプロジェクト
+    fn project_with(name: &str, existing: &[(&str, &str)]) -> (PathBuf, PathBuf, PackageReader) {
+        let dir = temp_dir(name);
+        let project = dir.join("proj");
+        std::fs::create_dir_all(project.join("Assets")).unwrap();
+        for (path, content) in existing {
+            std::fs::write(project.join(path), content).unwrap();
+        }
+        let package = dir.join("package.unitypackage");
+        write_package(&package, &[(GUID, "Assets/a.txt", Some(b"package"))]);
+        (dir, project, PackageReader::open(package).unwrap())
+    }
+
+    #[test]
+    fn plan_matches_extraction_when_renaming() {
+        // metaが衝突した場合はassetもmetaに合わせた名前で作成される
+        let (dir, project, reader) = project_with(
+            "plan-rename-meta",
+            &[("Assets/a.txt", "existing"), ("Assets/a.txt.meta", "existing meta")],
+        );
+        let options = ExtractOptions::default();
+        let plan = reader.plan_extract(&project, &options, OverwriteMode::Rename).unwrap();
+        assert_eq!(
+            actions(&plan.operations),
+            [
+                ("Assets/a.txt.meta", &rename("Assets/a_copy1.txt.meta")),
+                ("Assets/a.txt", &rename("Assets/a_copy1.txt")),
+            ]
+        );
+        reader
+            .extract(&project, &options, &mut SilentHandler::new(OverwriteMode::Rename))
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(project.join("Assets/a_copy1.txt")).unwrap(), "package");
+        assert!(project.join("Assets/a_copy1.txt.meta").is_file());
+        assert_eq!(std::fs::read_to_string(project.join("Assets/a.txt.meta")).unwrap(), "existing meta");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // metaのないassetが衝突した場合は、作成するmetaもassetと一緒にリネームされる
+        let (dir, project, reader) = project_with("plan-rename-asset", &[("Assets/a.txt", "existing")]);
+        let plan = reader.plan_extract(&project, &options, OverwriteMode::Rename).unwrap();
+        assert_eq!(
+            actions(&plan.operations),
+            [
+                ("Assets/a.txt.meta", &rename("Assets/a_copy1.txt.meta")),
+                ("Assets/a.txt", &rename("Assets/a_copy1.txt")),
+            ]
+        );
+        reader
+            .extract(&project, &options, &mut SilentHandler::new(OverwriteMode::Rename))
+            .unwrap();
+        assert!(!project.join("Assets/a.txt.meta").exists());
+        assert!(project.join("Assets/a_copy1.txt.meta").is_file());
+        assert_eq!(std::fs::read_to_string(project.join("Assets/a_copy1.txt")).unwrap(), "package");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn plan_uses_separate_modes_and_ask() {
+        let (dir, project, reader) = project_with(
+            "plan-modes",
+            &[("Assets/a.txt", "existing"), ("Assets/a.txt.meta", "existing meta")],
+        );
+        let modes = OverwriteModes {
+            meta: OverwriteMode::Skip,
+            asset: OverwriteMode::Overwrite,
+        };
+        let plan = reader.plan_extract(&project, &ExtractOptions::default(), modes).unwrap();
+        assert_eq!(
+            actions(&plan.operations),
+            [("Assets/a.txt.meta", &PlannedAction::Skip), ("Assets/a.txt", &PlannedAction::Overwrite)]
+        );
+
+        let plan = reader
+            .plan_extract(&project, &ExtractOptions::default(), OverwriteMode::Ask)
+            .unwrap();
+        assert_eq!(
+            actions(&plan.operations),
+            [("Assets/a.txt.meta", &PlannedAction::Ask), ("Assets/a.txt", &PlannedAction::Ask)]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn plan_compares_identical_files_only_when_enabled() {
+        let existing_meta = meta(GUID);
+        let (dir, project, reader) = project_with(
+            "plan-identical",
+            &[("Assets/a.txt", "package"), ("Assets/a.txt.meta", &existing_meta)],
+        );
+
+        let plan = reader
+            .plan_extract(&project, &ExtractOptions::default(), OverwriteMode::Skip)
+            .unwrap();
+        assert_eq!(
+            actions(&plan.operations),
+            [("Assets/a.txt.meta", &PlannedAction::Skip), ("Assets/a.txt", &PlannedAction::Skip)]
+        );
+
+        let options = ExtractOptions {
+            skip_identical: true,
+            ..ExtractOptions::default()
+        };
+        let plan = reader.plan_extract(&project, &options, OverwriteMode::Skip).unwrap();
+        assert_eq!(
+            actions(&plan.operations),
+            [("Assets/a.txt.meta", &PlannedAction::Identical), ("Assets/a.txt", &PlannedAction::Identical)]
+        );
+
+        // 新しいGUIDに書き換えたmetaは既存のmetaと異なる
+        let options = ExtractOptions {
+            skip_identical: true,
+            remap_guids: true,
+            ..ExtractOptions::default()
+        };
+        let plan = reader.plan_extract(&project, &options, OverwriteMode::Skip).unwrap();
+        assert_eq!(
+            actions(&plan.operations),
+            [("Assets/a.txt.meta", &PlannedAction::Skip), ("Assets/a.txt", &PlannedAction::Identical)]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn plan_treats_planned_files_as_existing() {
+        let dir = temp_dir("plan-planned");
+        let package = dir.join("package.unitypackage");
+        write_package(
+            &package,
+            &[
+                ("00000000000000000000000000000001", "Assets/Foo", None),
+                ("00000000000000000000000000000002", "Assets/Foo/a.txt", Some(b"a")),
+            ],
+        );
+        let plan = PackageReader::open(&package)
+            .unwrap()
+            .plan_extract(dir.join("proj"), &ExtractOptions::default(), OverwriteMode::Skip)
+            .unwrap();
+        assert_eq!(
+            actions(&plan.operations),
+            [
+                ("Assets/Foo", &PlannedAction::Create),
+                ("Assets/Foo.meta", &PlannedAction::Create),
+                ("Assets/Foo/a.txt.meta", &PlannedAction::Create),
+                ("Assets/Foo/a.txt", &PlannedAction::Create),
+            ]
+        );
+        assert!(plan.operations[0].is_folder);
+        assert!(!dir.join("proj").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn plan_rejects_entries_of_unknown_kind() {
+        let entries = [ListEntry {
+            guid: GUID.to_string(),
+            pathname: Some("Assets/Broken".to_string()),
+            meta: Some(meta(GUID)),
+            ..Default::default()
+        }];
+        let dir = temp_dir("plan-unknown");
+        let result = plan_extraction(
+            &entries,
+            &dir,
+            OverwriteModes::from(OverwriteMode::Skip),
+            &[],
+            &ExtractOptions::default(),
+        );
+        assert!(matches!(result, Err(Error::UnknownEntryKind { .. })), "{:?}", result);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::filter::matches_self_or_ancestor;
+use crate::ui::OverwriteMode;
+use std::path::Path;
+
+/// pathnameのglobごとの上書きモード
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverwriteRule {
+    pub pattern: String,
+    pub mode: OverwriteMode,
+}
+
+/// pathnameごとに上書きモードを決める規則の一覧
+///
+/// 先に書いた規則から順に照合し、最初にマッチした規則のモードを使用する。
+/// パターンの書式は `--include`/`--exclude` と同じで、フォルダにマッチした場合は中身にも適用する。
+/// どの規則にもマッチしない場合は `--overwrite-mode` などの全体の設定に従う。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OverwritePolicy {
+    pub rules: Vec<OverwriteRule>,
+}
+
+impl OverwritePolicy {
+    /// ポリシーファイルを読み込む
+    ///
+    /// 1行に1つ `<glob> <mode>` の形式で書く（modeは overwrite, skip, rename）。
+    /// 空行と `#` で始まる行は無視する。
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(Error::io(IoOperation::Read, path))?;
+        Self::parse(&content).map_err(|(line, reason)| Error::InvalidPolicy {
+            path: path.to_path_buf(),
+            line,
+            reason,
+        })
+    }
+
+    /// ポリシーファイルの内容を解析する
+    /// 失敗した場合は行番号（1始まり）と理由を返す
+    pub fn parse(content: &str) -> std::result::Result<Self, (usize, String)> {
+        let mut rules = Vec::new();
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((pattern, mode)) = line.rsplit_once(char::is_whitespace) else {
+                return Err((idx + 1, format!("`<glob> <mode>` の形式ではありません: {}", line)));
+            };
+            let mode = match mode {
+                "overwrite" => OverwriteMode::Overwrite,
+                "skip" => OverwriteMode::Skip,
+                "rename" => OverwriteMode::Rename,
+                _ => {
+                    return Err((
+                        idx + 1,
+                        format!("不明なモードです: {}（overwrite, skip, rename のいずれか）", mode),
+                    ));
+                }
+            };
+            rules.push(OverwriteRule {
+                pattern: pattern.trim_end().to_string(),
+                mode,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    /// 規則が指定されていないか
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// pathnameに最初にマッチした規則のモード
+    pub fn mode_for(&self, pathname: &str) -> Option<OverwriteMode> {
+        self.rules
+            .iter()
+            .find(|rule| matches_self_or_ancestor(&rule.pattern, pathname))
+            .map(|rule| rule.mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_rules_and_skips_comments() {
+        let policy = OverwritePolicy::parse(
+            "# 設定ファイルは上書きしない\n\nAssets/Foo/Settings/**  skip\n  Assets/Foo  overwrite  \nAssets/My Folder/*.mat rename\n",
+        )
+        .unwrap();
+        assert_eq!(
+            policy.rules,
+            vec![
+                OverwriteRule {
+                    pattern: "Assets/Foo/Settings/**".to_string(),
+                    mode: OverwriteMode::Skip,
+                },
+                OverwriteRule {
+                    pattern: "Assets/Foo".to_string(),
+                    mode: OverwriteMode::Overwrite,
+                },
+                OverwriteRule {
+                    pattern: "Assets/My Folder/*.mat".to_string(),
+                    mode: OverwriteMode::Rename,
+                },
+            ]
+        );
+        assert!(OverwritePolicy::parse("# comment\n\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_reports_the_line_of_an_unknown_mode() {
+        let (line, reason) = OverwritePolicy::parse("Assets/Foo skip\n\nAssets/Bar replace\n").unwrap_err();
+        assert_eq!(line, 3);
+        assert!(reason.contains("replace"), "{}", reason);
+    }
+
+    #[test]
+    fn parse_rejects_a_line_without_mode() {
+        let (line, _) = OverwritePolicy::parse("# comment\nAssets/Foo\n").unwrap_err();
+        assert_eq!(line, 2);
+    }
+
+    #[test]
+    fn mode_for_uses_the_first_matching_rule() {
+        let policy = OverwritePolicy::parse("Assets/Foo/Settings skip\nAssets/Foo overwrite\n**/*.asset rename\n").unwrap();
+        assert_eq!(policy.mode_for("Assets/Foo/Settings/A.asset"), Some(OverwriteMode::Skip));
+        assert_eq!(policy.mode_for("Assets/Foo/B.asset"), Some(OverwriteMode::Overwrite));
+        assert_eq!(policy.mode_for("Assets/Bar/C.asset"), Some(OverwriteMode::Rename));
+        assert_eq!(policy.mode_for("Assets/Bar/D.mat"), None);
+        assert_eq!(policy.mode_for("Assets/FooBar/E.mat"), None);
+    }
+}
@@ -0,0 +1,19 @@
+use crate::core::error::{Error, IoOperation, Result};
+use std::path::{Path, PathBuf};
+
+/// プレビュー画像の出力先
+/// `<dir>/<pathname>.png` の形でアセットと同じ階層構造にする
+pub fn preview_path(dir: &Path, pathname: &str) -> PathBuf {
+    dir.join(format!("{}.png", pathname))
+}
+
+/// プレビュー画像を書き出す（既存のファイルは上書きする）
+pub fn export_preview(dir: &Path, pathname: &str, data: &[u8]) -> Result<()> {
+    let path = preview_path(dir, pathname);
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        std::fs::create_dir_all(parent).map_err(Error::io(IoOperation::CreateDir, parent))?;
+    }
+    std::fs::write(&path, data).map_err(Error::io(IoOperation::Write, &path))
+}
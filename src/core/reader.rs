@@ -0,0 +1,516 @@
+use crate::core::diff::{diff_entries, DiffEntry};
+use crate::core::direct::extract_direct;
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::entry::PackageEntry;
+use crate::core::extract::{extract_objects, ExtractOptions};
+use crate::core::filter::PathFilter;
+use crate::core::guid::GuidRemap;
+use crate::core::guid_index::{find_project_conflicts, GuidConflict, GuidConflictKind};
+use crate::core::list::{list_entries, scan_entries, select_entries, ListEntry};
+use crate::core::path::check_pathnames;
+use crate::core::plan::{plan_extraction, PlannedOperation};
+use crate::core::rebuild::rebuild_objects;
+use crate::core::uninstall::{uninstall_package, UninstallSummary};
+use crate::core::verify::{verify_package, VerifyIssue};
+use crate::core::upgrade::{apply_upgrade, path_after_upgrade, plan_upgrade, UpgradeMove};
+use crate::ui::{OverwriteModes, UiHandler};
+use std::path::{Path, PathBuf};
+
+/// 通常モードで使用する一時ディレクトリ名（出力先ディレクトリ直下に作成）
+pub const TMP_OUTPUT_DIR: &str = ".jp.ootr.unitypackage-extractor";
+
+/// 展開前の確認の結果（`PackageReader::preflight`）
+#[derive(Debug, Clone)]
+pub struct ExtractPreflight {
+    entries: Vec<ListEntry>,
+    upgrade_moves: Vec<UpgradeMove>,
+    conflicts: Vec<GuidConflict>,
+}
+
+impl ExtractPreflight {
+    /// 展開対象のエントリ（付け替え後のpathname、`sha256` は計算しない）
+    pub fn entries(&self) -> &[ListEntry] {
+        &self.entries
+    }
+
+    /// 展開前に移動する既存のアセット（`options.upgrade` が有効な場合）
+    pub fn upgrade_moves(&self) -> &[UpgradeMove] {
+        &self.upgrade_moves
+    }
+
+    /// 展開先プロジェクトの既存のアセットとのGUIDの衝突
+    pub fn conflicts(&self) -> &[GuidConflict] {
+        &self.conflicts
+    }
+}
+
+/// 展開の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractSummary {
+    /// 展開前に移動した既存のアセット（`options.upgrade` が有効な場合）
+    pub upgrade_moves: Vec<UpgradeMove>,
+    /// 確認のうえ続行した、展開先プロジェクトの既存のアセットとのGUIDの衝突
+    pub conflicts: Vec<GuidConflict>,
+}
+
+/// ドライランの結果（`PackageReader::plan_extract`）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractPlan {
+    /// 各ファイル・フォルダの処理
+    pub operations: Vec<PlannedOperation>,
+    /// 展開前に移動する既存のアセット（`options.upgrade` が有効な場合）
+    pub upgrade_moves: Vec<UpgradeMove>,
+    /// 展開先プロジェクトの既存のアセットとのGUIDの衝突
+    pub conflicts: Vec<GuidConflict>,
+}
+
+/// .unitypackageファイルの読み込み
+pub struct PackageReader {
+    path: PathBuf,
+}
+
+impl PackageReader {
+    /// パッケージを開く
+    /// ファイルが存在しない場合は `Error::InputNotFound` を返す
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if !path.exists() {
+            return Err(Error::InputNotFound { path });
+        }
+        Ok(Self { path })
+    }
+
+    /// パッケージファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 展開せずにエントリ一覧を取得
+    pub fn entries(&self) -> Result<Vec<ListEntry>> {
+        list_entries(&self.path)
+    }
+
+    /// 別のパッケージ（新しいバージョンなど）とエントリをGUIDで対応付けて比較
+    pub fn diff(&self, new: &PackageReader) -> Result<Vec<DiffEntry>> {
+        Ok(diff_entries(&self.entries()?, &new.entries()?))
+    }
+
+    /// 展開せずにパッケージの構造を検証し、見つかった問題をすべて取得
+    pub fn verify(&self) -> Result<Vec<VerifyIssue>> {
+        verify_package(&self.path)
+    }
+
+    /// フィルタで展開対象になるエントリ一覧を取得（親フォルダを含む）
+    pub fn selected_entries(&self, filter: &PathFilter) -> Result<Vec<ListEntry>> {
+        Ok(select_entries(self.entries()?, filter))
+    }
+
+    /// 展開対象になるエントリ一覧を、pathnameを付け替えた展開先のパスで取得
+    /// 付け替え先のないエントリは含めない
+    pub fn target_entries(&self, options: &ExtractOptions) -> Result<Vec<ListEntry>> {
+        Ok(relocate_entries(self.selected_entries(&options.filter)?, options))
+    }
+
+    /// `target_entries` と同じエントリを、`hash_assets` が有効な場合だけassetのハッシュを計算して取得
+    fn scan_target_entries(&self, options: &ExtractOptions, hash_assets: bool) -> Result<Vec<ListEntry>> {
+        let entries = select_entries(scan_entries(&self.path, hash_assets)?, &options.filter);
+        Ok(relocate_entries(entries, options))
+    }
+
+    /// このパッケージで展開した、内容が変更されていないアセットをプロジェクトから削除
+    pub fn uninstall(&self, project_dir: impl AsRef<Path>) -> Result<UninstallSummary> {
+        uninstall_package(&self.entries()?, project_dir.as_ref())
+    }
+
+    /// ディスクに書き込まずに、展開時に作成・上書き・スキップ・リネームされるファイルを判定
+    ///
+    /// `preflight` と同じ確認を行ったうえで各ファイルの処理を判定する。アーカイブは一度だけ読み、
+    /// assetのハッシュは `options.skip_identical` が有効な場合だけ計算する。
+    pub fn plan_extract(
+        &self,
+        output_dir: impl AsRef<Path>,
+        options: &ExtractOptions,
+        overwrite_modes: impl Into<OverwriteModes>,
+    ) -> Result<ExtractPlan> {
+        let output_dir = output_dir.as_ref();
+        let entries = self.scan_target_entries(options, options.skip_identical)?;
+        let (upgrade_moves, conflicts) = check_targets(&target_pathnames(&entries), output_dir, options)?;
+        let operations = plan_extraction(&entries, output_dir, overwrite_modes.into(), &upgrade_moves, options)?;
+        Ok(ExtractPlan {
+            operations,
+            upgrade_moves,
+            conflicts,
+        })
+    }
+
+    /// 展開前の確認を行う
+    ///
+    /// 展開対象のエントリをassetのハッシュを計算せずに読み込み、pathnameを検証する
+    /// （`options.allow_unsafe_paths` が無効な場合）。続けて `options.upgrade` が有効な場合は
+    /// 移動する既存のアセットを判定し、展開先プロジェクトの既存のmetaとGUIDが衝突するエントリを探す。
+    pub fn preflight(&self, output_dir: impl AsRef<Path>, options: &ExtractOptions) -> Result<ExtractPreflight> {
+        let entries = self.scan_target_entries(options, false)?;
+        let (upgrade_moves, conflicts) = check_targets(&target_pathnames(&entries), output_dir.as_ref(), options)?;
+        Ok(ExtractPreflight {
+            entries,
+            upgrade_moves,
+            conflicts,
+        })
+    }
+
+    /// 出力先ディレクトリにパッケージを展開
+    ///
+    /// 既存ファイルとの衝突時の挙動は `ui_handler` の `confirm_overwrite` で決まる。
+    /// 展開先プロジェクトの既存のアセットとGUIDが衝突する場合は、書き込みを始める前に
+    /// `ui_handler` の `confirm_guid_conflicts` で続けるか確認する（`options.fail_on_guid_conflict` の場合はエラー）。
+    /// 通常はアーカイブを一度だけ読み、一時ディレクトリに展開したエントリで確認してから配置する。
+    /// `options.direct` の場合は書き込みながら読み進めるため、確認のためにアーカイブを事前に一度読む。
+    pub fn extract<U: UiHandler>(
+        &self,
+        output_dir: impl AsRef<Path>,
+        options: &ExtractOptions,
+        ui_handler: &mut U,
+    ) -> Result<ExtractSummary> {
+        let output_dir = output_dir.as_ref();
+        if !options.direct {
+            return self.extract_staged(output_dir, options, ui_handler);
+        }
+
+        let preflight = self.preflight(output_dir, options)?;
+        confirm_conflicts(&preflight.conflicts, options, ui_handler)?;
+        apply_upgrade(output_dir, &preflight.upgrade_moves)?;
+        let remap = options
+            .remap_guids
+            .then(|| GuidRemap::new(preflight.entries.iter().map(|entry| entry.guid.as_str())));
+        extract_direct(&self.path, output_dir, options, remap.as_ref(), ui_handler)?;
+
+        Ok(ExtractSummary {
+            upgrade_moves: preflight.upgrade_moves,
+            conflicts: preflight.conflicts,
+        })
+    }
+
+    fn extract_staged<U: UiHandler>(
+        &self,
+        output_dir: &Path,
+        options: &ExtractOptions,
+        ui_handler: &mut U,
+    ) -> Result<ExtractSummary> {
+        let tmp_output_dir = output_dir.join(TMP_OUTPUT_DIR);
+        if tmp_output_dir.exists() {
+            std::fs::remove_dir_all(&tmp_output_dir)
+                .map_err(Error::io(IoOperation::Remove, &tmp_output_dir))?;
+        }
+
+        // 失敗時も含めて確実に一時ディレクトリを削除するためのガード
+        struct TempDirGuard {
+            path: PathBuf,
+        }
+        impl Drop for TempDirGuard {
+            fn drop(&mut self) {
+                if self.path.exists()
+                    && let Err(e) = std::fs::remove_dir_all(&self.path)
+                {
+                    eprintln!("警告: 一時ディレクトリの削除に失敗しました: {}", e);
+                }
+            }
+        }
+        let _tmp_guard = TempDirGuard { path: tmp_output_dir.clone() };
+
+        // 抽出
+        let entries = extract_objects(&self.path, &tmp_output_dir, options, ui_handler)?;
+
+        // キャンセルチェック
+        if ui_handler.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        // 付け替え先のないエントリは展開しない
+        let entries: Vec<PackageEntry> = if options.relocation.is_empty() {
+            entries
+        } else {
+            entries
+                .into_iter()
+                .filter_map(|entry| {
+                    let pathname = options.relocation.apply(&entry.pathname)?;
+                    Some(PackageEntry { pathname, ..entry })
+                })
+                .collect()
+        };
+
+        // パッケージを読み終えてから、出力先に書き込む前に確認し、既存のアセットを移動する
+        let pathnames: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|entry| (entry.guid.as_str(), entry.pathname.as_str()))
+            .collect();
+        let (upgrade_moves, conflicts) = check_targets(&pathnames, output_dir, options)?;
+        confirm_conflicts(&conflicts, options, ui_handler)?;
+        apply_upgrade(output_dir, &upgrade_moves)?;
+
+        // 再構築
+        rebuild_objects(&entries, output_dir, options, ui_handler)?;
+
+        Ok(ExtractSummary {
+            upgrade_moves,
+            conflicts,
+        })
+    }
+}
+
+/// 展開先のpathname（GUID, pathname）を検証し、アップグレード時の移動とGUIDの衝突を判定する
+fn check_targets(
+    pathnames: &[(&str, &str)],
+    output_dir: &Path,
+    options: &ExtractOptions,
+) -> Result<(Vec<UpgradeMove>, Vec<GuidConflict>)> {
+    // 既存のアセットを移動する前に、移動先になるpathnameを検証する
+    if !options.allow_unsafe_paths {
+        check_pathnames(pathnames.iter().copied())?;
+    }
+
+    let upgrade_moves = if options.upgrade { plan_upgrade(pathnames, output_dir)? } else { Vec::new() };
+
+    // 移動や新しいGUIDで解消される衝突は含めない
+    let mut conflicts = find_project_conflicts(pathnames, output_dir)?;
+    if options.remap_guids {
+        conflicts.retain(|conflict| matches!(conflict.kind, GuidConflictKind::PathHasOtherGuid { .. }));
+    }
+    conflicts.retain(|conflict| match &conflict.kind {
+        GuidConflictKind::GuidAtOtherPath { existing_paths } => !existing_paths
+            .iter()
+            .any(|path| path_after_upgrade(path, &upgrade_moves) == conflict.pathname),
+        GuidConflictKind::PathHasOtherGuid { .. } => true,
+    });
+
+    Ok((upgrade_moves, conflicts))
+}
+
+/// GUIDの衝突がある場合、`options.fail_on_guid_conflict` ならエラーにし、それ以外は続けるか確認する
+fn confirm_conflicts<U: UiHandler>(conflicts: &[GuidConflict], options: &ExtractOptions, ui_handler: &mut U) -> Result<()> {
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    if options.fail_on_guid_conflict || !ui_handler.confirm_guid_conflicts(conflicts) {
+        return Err(Error::GuidConflict {
+            conflicts: conflicts.to_vec(),
+        });
+    }
+    Ok(())
+}
+
+/// pathnameのあるエントリの（GUID, pathname）
+fn target_pathnames(entries: &[ListEntry]) -> Vec<(&str, &str)> {
+    entries
+        .iter()
+        .filter_map(|entry| entry.pathname.as_deref().map(|pathname| (entry.guid.as_str(), pathname)))
+        .collect()
+}
+
+/// pathnameを付け替え先のパスにする（付け替え先のないエントリは含めない）
+fn relocate_entries(entries: Vec<ListEntry>, options: &ExtractOptions) -> Vec<ListEntry> {
+    if options.relocation.is_empty() {
+        return entries;
+    }
+    entries
+        .into_iter()
+        .filter_map(|mut entry| {
+            entry.pathname = Some(options.relocation.apply(entry.pathname.as_deref()?)?);
+            Some(entry)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{meta, temp_dir, write_package};
+    use crate::core::writer::PackageWriter;
+    use crate::ui::{OverwriteAction, OverwriteMode, OverwriteTarget, SilentHandler};
+
+    const GUID: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn upgrade_does_not_move_assets_to_unsafe_pathnames() {
+        for direct in [false, true] {
+            let dir = temp_dir(if direct { "upgrade-unsafe-direct" } else { "upgrade-unsafe" });
+            let project = dir.join("proj");
+            std::fs::create_dir_all(project.join("Assets")).unwrap();
+            std::fs::write(project.join("Assets/a.txt"), "existing").unwrap();
+            std::fs::write(project.join("Assets/a.txt.meta"), meta(GUID)).unwrap();
+
+            let asset = dir.join("asset.txt");
+            std::fs::write(&asset, "package").unwrap();
+            let package = dir.join("evil.unitypackage");
+            let mut writer = PackageWriter::create(&package).unwrap();
+            writer
+                .append_entry(GUID, "Assets/../../escaped.txt", &meta(GUID), Some(&asset))
+                .unwrap();
+            writer.finish().unwrap();
+
+            let options = ExtractOptions {
+                upgrade: true,
+                direct,
+                ..ExtractOptions::default()
+            };
+            let result = PackageReader::open(&package).unwrap().extract(
+                &project,
+                &options,
+                &mut SilentHandler::new(OverwriteMode::Overwrite),
+            );
+
+            assert!(matches!(result, Err(Error::UnsafePath { .. })), "{:?}", result);
+            assert_eq!(std::fs::read_to_string(project.join("Assets/a.txt")).unwrap(), "existing");
+            assert!(project.join("Assets/a.txt.meta").is_file());
+            assert!(!dir.join("escaped.txt").exists());
+            assert!(!dir.join("escaped.txt.meta").exists());
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn direct_extract_writes_nothing_when_a_pathname_is_unsafe() {
+        let dir = temp_dir("direct-unsafe");
+        let asset = dir.join("asset.txt");
+        std::fs::write(&asset, "package").unwrap();
+
+        // 安全なエントリをアーカイブの先に置く
+        let package = dir.join("evil.unitypackage");
+        let mut writer = PackageWriter::create(&package).unwrap();
+        writer
+            .append_entry(GUID, "Assets/safe.txt", &meta(GUID), Some(&asset))
+            .unwrap();
+        let unsafe_guid = "fedcba9876543210fedcba9876543210";
+        writer
+            .append_entry(unsafe_guid, "../escaped.txt", &meta(unsafe_guid), Some(&asset))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let project = dir.join("proj");
+        let options = ExtractOptions {
+            direct: true,
+            ..ExtractOptions::default()
+        };
+        let result = PackageReader::open(&package).unwrap().extract(
+            &project,
+            &options,
+            &mut SilentHandler::new(OverwriteMode::Overwrite),
+        );
+
+        assert!(matches!(result, Err(Error::UnsafePath { .. })), "{:?}", result);
+        assert!(!project.join("Assets/safe.txt").exists());
+        assert!(!dir.join("escaped.txt").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// GUIDの衝突の確認に決まった応答を返し、確認した件数を記録するハンドラー
+    struct ConflictHandler {
+        accept: bool,
+        confirmed: Vec<GuidConflict>,
+    }
+
+    impl UiHandler for ConflictHandler {
+        fn update_progress(&mut self, _value: f32, _text: &str) {}
+
+        fn finish(&mut self) {}
+
+        fn confirm_overwrite(&mut self, _path: &str, _target: OverwriteTarget) -> OverwriteAction {
+            OverwriteAction::Skip
+        }
+
+        fn is_cancelled(&self) -> bool {
+            false
+        }
+
+        fn confirm_guid_conflicts(&mut self, conflicts: &[GuidConflict]) -> bool {
+            self.confirmed.extend_from_slice(conflicts);
+            self.accept
+        }
+    }
+
+    /// 同じGUIDのアセットが別のパスにあるプロジェクトと、そのGUIDを含むパッケージ
+    fn conflicting_project(name: &str) -> (PathBuf, PathBuf, PathBuf) {
+        let dir = temp_dir(name);
+        let project = dir.join("proj");
+        std::fs::create_dir_all(project.join("Assets")).unwrap();
+        std::fs::write(project.join("Assets/old.txt"), "existing").unwrap();
+        std::fs::write(project.join("Assets/old.txt.meta"), meta(GUID)).unwrap();
+
+        let package = dir.join("package.unitypackage");
+        write_package(&package, &[(GUID, "Assets/new.txt", Some(b"package"))]);
+        (dir, project, package)
+    }
+
+    #[test]
+    fn extract_stops_before_writing_when_guid_conflicts_are_declined() {
+        for direct in [false, true] {
+            let (dir, project, package) =
+                conflicting_project(if direct { "conflict-decline-direct" } else { "conflict-decline" });
+            let options = ExtractOptions {
+                direct,
+                ..ExtractOptions::default()
+            };
+            let mut handler = ConflictHandler {
+                accept: false,
+                confirmed: Vec::new(),
+            };
+            let result = PackageReader::open(&package).unwrap().extract(&project, &options, &mut handler);
+
+            assert!(matches!(result, Err(Error::GuidConflict { .. })), "{:?}", result);
+            assert_eq!(handler.confirmed.len(), 1);
+            assert_eq!(handler.confirmed[0].pathname, "Assets/new.txt");
+            assert!(!project.join("Assets/new.txt").exists());
+            assert!(!project.join("Assets/new.txt.meta").exists());
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn extract_fails_on_guid_conflicts_without_asking() {
+        let (dir, project, package) = conflicting_project("conflict-fail");
+        let options = ExtractOptions {
+            fail_on_guid_conflict: true,
+            ..ExtractOptions::default()
+        };
+        let mut handler = ConflictHandler {
+            accept: true,
+            confirmed: Vec::new(),
+        };
+        let result = PackageReader::open(&package).unwrap().extract(&project, &options, &mut handler);
+
+        assert!(matches!(result, Err(Error::GuidConflict { .. })), "{:?}", result);
+        assert_eq!(handler.confirmed, []);
+        assert!(!project.join("Assets/new.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn extract_reports_accepted_guid_conflicts_and_upgrade_moves() {
+        let (dir, project, package) = conflicting_project("conflict-accept");
+        let reader = PackageReader::open(&package).unwrap();
+
+        let summary = reader
+            .extract(&project, &ExtractOptions::default(), &mut SilentHandler::new(OverwriteMode::Skip))
+            .unwrap();
+        assert_eq!(summary.conflicts.len(), 1);
+        assert_eq!(summary.upgrade_moves, []);
+        assert_eq!(std::fs::read_to_string(project.join("Assets/new.txt")).unwrap(), "package");
+        std::fs::remove_file(project.join("Assets/new.txt")).unwrap();
+        std::fs::remove_file(project.join("Assets/new.txt.meta")).unwrap();
+
+        // 移動で解消される衝突は含めない
+        let options = ExtractOptions {
+            upgrade: true,
+            ..ExtractOptions::default()
+        };
+        let summary = reader
+            .extract(&project, &options, &mut SilentHandler::new(OverwriteMode::Overwrite))
+            .unwrap();
+        assert_eq!(summary.conflicts, []);
+        assert_eq!(summary.upgrade_moves.len(), 1);
+        assert_eq!(std::fs::read_to_string(project.join("Assets/new.txt")).unwrap(), "package");
+        assert!(!project.join("Assets/old.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
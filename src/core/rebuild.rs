@@ -1,210 +1,484 @@
-use crate::ui::{UiHandler, OverwriteAction};
-use yaml_rust::YamlLoader;
+use crate::core::backup::backup_file;
+use crate::core::entry::{EntryKind, PackageEntry};
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::extract::ExtractOptions;
+use crate::core::guid::{generate_guid, replace_meta_guid, rewrite_guid_references, GuidRemap, is_text_serialized};
+use crate::core::guid_index::read_meta_guid;
+use crate::core::list::{file_sha256, hex_string};
+use crate::core::preview::export_preview;
+use crate::ui::{UiHandler, OverwriteAction, OverwriteMode, OverwriteTarget};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
-const ASSET_META_FILENAME: &str = "asset.meta";
-const PATHNAME_FILENAME: &str = "pathname";
+/// assetの書き込み元
+pub enum AssetSource<'a> {
+    /// 展開済みのファイルを移動する
+    File(&'a Path),
+    /// アーカイブのストリームから直接書き込む（サイズはアーカイブのヘッダーの値）
+    Reader(&'a mut dyn Read, u64),
+    /// 書き換え済みの内容を書き込む
+    Data(&'a [u8]),
+}
 
+/// 展開済みのエントリを出力先に配置する
+/// `entries` のpathnameは付け替え・検証済みのものを渡す（`PackageReader::extract`）
 pub fn rebuild_objects<U: UiHandler>(
-    objects: &HashMap<String, HashMap<String, String>>,
+    entries: &[PackageEntry],
     output_dir: &Path,
-    source_dir: &Path,
+    options: &ExtractOptions,
     ui_handler: &mut U,
-) -> Result<(), String> {
-    let total = objects.len() as f32;
+) -> Result<()> {
+    let total = entries.len() as f32;
     ui_handler.update_progress(0.0, "開始");
 
-    let mut idx = 0u32;
-    for (folder, files) in objects {
+    let remap = options
+        .remap_guids
+        .then(|| GuidRemap::new(entries.iter().map(|entry| entry.guid.as_str())));
+
+    let mut copies = Vec::new();
+    for (idx, entry) in entries.iter().enumerate() {
         // キャンセルチェック
         if ui_handler.is_cancelled() {
-            return Err("キャンセルされました".to_string());
-        }
-
-        idx += 1;
-        let pathname = files.get(PATHNAME_FILENAME)
-            .ok_or("pathnameが見つかりません")?;
-        let asset_meta = files.get(ASSET_META_FILENAME)
-            .ok_or("asset.metaが見つかりません")?;
-
-        ui_handler.update_progress(idx as f32 / total, pathname);
-
-        let asset_meta_yaml = YamlLoader::load_from_str(asset_meta)
-            .map_err(|e| format!("{}のmetaファイルのパースに失敗しました: {}", pathname, e))?;
-        let asset_meta_yaml = asset_meta_yaml.get(0)
-            .ok_or("metaファイルのルートが見つかりません")?;
-        
-        let source_file_path = source_dir.join(&folder);
-
-        // フォルダかどうかの判定:
-        // 1. metaファイルにfolderAsset: yesがある
-        // 2. source_file_pathが存在しない（フォルダアセットは空ファイルなので展開時に存在しない）
-        // 3. source_file_pathがディレクトリとして存在する
-        let is_folder_by_meta = asset_meta_yaml["folderAsset"].as_str().unwrap_or("false") == "yes";
-        let is_folder_by_fs = source_file_path.exists() && source_file_path.is_dir();
-        let is_dir = is_folder_by_meta || !source_file_path.exists() || is_folder_by_fs;
-
-        if is_dir {
-            handle_directory(output_dir, pathname, asset_meta)?;
-            continue;
+            return Err(Error::Cancelled);
+        }
+
+        ui_handler.update_progress((idx + 1) as f32 / total, &entry.pathname);
+
+        let meta = match &remap {
+            Some(remap) => remap.remap_meta(&entry.meta),
+            None => entry.meta.clone(),
+        };
+
+        match entry.kind {
+            EntryKind::Folder => {
+                handle_directory(output_dir, &entry.pathname, &meta)?;
+            }
+            EntryKind::File => {
+                // 展開に失敗したassetをフォルダとして扱わないよう、明示的にエラーにする
+                let asset_location = entry.asset_location.as_ref().ok_or_else(|| Error::MissingAsset {
+                    guid: entry.guid.clone(),
+                    pathname: entry.pathname.clone(),
+                })?;
+                if let Some(remap) = &remap {
+                    remap_asset_file(remap, &entry.pathname, asset_location)?;
+                }
+                let copy = handle_file(
+                    output_dir,
+                    &entry.pathname,
+                    &meta,
+                    AssetSource::File(asset_location),
+                    options,
+                    ui_handler,
+                )?;
+                copies.extend(copy);
+            }
         }
 
-        handle_file(
-            output_dir,
-            pathname,
-            asset_meta,
-            &source_file_path,
-            ui_handler,
-        )?;
+        if let (Some(preview_dir), Some(preview)) = (&options.export_previews, &entry.preview) {
+            export_preview(preview_dir, &entry.pathname, preview)?;
+        }
+    }
+
+    if options.rewrite_renamed_references && !copies.is_empty() {
+        rewrite_renamed_references(&copies)?;
     }
 
     ui_handler.finish();
     Ok(())
 }
 
-fn handle_directory(
+/// 一時ディレクトリに展開したassetの参照を新しいGUIDに書き換える
+fn remap_asset_file(remap: &GuidRemap, pathname: &str, asset_location: &Path) -> Result<()> {
+    if !is_text_serialized(pathname) {
+        return Ok(());
+    }
+    let content = std::fs::read(asset_location).map_err(Error::io(IoOperation::Read, asset_location))?;
+    if let Some(remapped) = remap.remap_asset(pathname, &content) {
+        std::fs::write(asset_location, remapped).map_err(Error::io(IoOperation::Write, asset_location))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn handle_directory(
     output_dir: &Path,
     pathname: &str,
     asset_meta: &str,
-) -> Result<(), String> {
+) -> Result<()> {
     let output_path = output_dir.join(pathname);
     if !output_path.exists() {
         std::fs::create_dir_all(&output_path)
-            .map_err(|e| format!("Output directory creation failed: {}", e))?;
+            .map_err(Error::io(IoOperation::CreateDir, &output_path))?;
     }
 
-    let folder_name = pathname.split('/').last().unwrap_or("");
+    let folder_name = pathname.split('/').next_back().unwrap_or("");
     let meta_path = output_path
         .parent()
         .unwrap()
         .join(format!("{}.meta", folder_name));
 
     if !meta_path.exists() {
-        let mut meta_file = File::create(meta_path)
-            .map_err(|e| format!("metaファイル作成失敗: {}", e))?;
+        let mut meta_file = File::create(&meta_path)
+            .map_err(Error::io(IoOperation::Create, &meta_path))?;
         meta_file
             .write_all(asset_meta.as_bytes())
-            .map_err(|e| format!("Failed to write folder meta file: {}", e))?;
+            .map_err(Error::io(IoOperation::Write, &meta_path))?;
     }
 
     Ok(())
 }
 
-fn handle_file<U: UiHandler>(
-    output_dir: &Path,
-    pathname: &str,
-    asset_meta: &str,
-    source_file_path: &Path,
-    ui_handler: &mut U,
-) -> Result<(), String> {
-    let output_file_path = output_dir.join(pathname);
-    let output_basedir = output_file_path.parent().unwrap();
+/// リネームして配置したコピー
+/// 元のアセットとGUIDが重複しないよう、新しいGUIDを割り当てている
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamedCopy {
+    pub old_guid: String,
+    pub new_guid: String,
+    pub asset_path: PathBuf,
+}
 
-    if !output_basedir.exists() {
-        std::fs::create_dir_all(&output_basedir)
-            .map_err(|e| format!("Output directory creation failed: {}", e))?;
-    }
+/// 既存のファイルに対する処理
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Placement {
+    /// 新規に作成する
+    Create,
+    /// 既存のファイルと内容が同じためそのまま残す
+    Identical,
+    /// 既存のファイルを上書きする
+    Overwrite,
+    /// 同じフォルダに別名（ファイル名）で作成する
+    Rename { to: String },
+    /// 既存のファイルを残す
+    Skip,
+    /// 実行時の確認で決まる（ドライランのみ）
+    Ask,
+}
 
-    let file_name = pathname.split('/').last().unwrap_or("");
-    let meta_path = output_file_path
-        .parent()
-        .unwrap()
-        .join(format!("{}.meta", file_name));
+/// 1つのファイルのmetaとassetの処理（`handle_file` とドライランで共通の判定結果）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FilePlacement {
+    /// `<ファイル名>.meta` の処理（Renameの場合は元の名前には書き込まず、新しい名前のmetaだけを作成する）
+    pub meta: Placement,
+    /// assetの処理を判定したファイル名（metaをリネームした場合はその名前）
+    pub asset_name: String,
+    /// assetの処理（Renameの場合は同じ名前のmetaも作成する）
+    pub asset: Placement,
+    /// リネームしたコピーに新しいGUIDを割り当てる（元の名前にmetaが残る場合だけ）
+    pub new_guid: bool,
+}
+
+/// 既存のファイルの確認方法（展開時はディスクとUI、ドライランでは作成予定のパスと上書きモードを使う）
+pub(crate) trait PlacementCheck {
+    /// パスにファイルまたはフォルダがあるか
+    fn exists(&self, path: &Path) -> bool;
+    /// 既存のmetaが書き込む内容と同じか（`skip_identical` が無効な場合はfalse）
+    fn meta_identical(&mut self, path: &Path) -> Result<bool>;
+    /// 既存のassetが書き込む内容と同じか（`skip_identical` が無効な場合はfalse）
+    fn asset_identical(&mut self, path: &Path) -> Result<bool>;
+    /// 既存のファイルの扱いを決める（`None` は実行時の確認に委ねる）
+    fn resolve(&mut self, path: &str, target: OverwriteTarget) -> Option<OverwriteAction>;
+}
 
-    let mut skip_asset = false;
-    let mut asset_rename: Option<String> = None;
+/// metaとassetをどう配置するか判定する（ディスクには書き込まない）
+pub(crate) fn place_file(check: &mut impl PlacementCheck, parent_dir: &Path, pathname: &str) -> Result<FilePlacement> {
+    let file_name = pathname.split('/').next_back().unwrap_or("");
+    let meta_path = parent_dir.join(format!("{}.meta", file_name));
+    let mut asset_name = file_name.to_string();
+    let mut new_guid = false;
 
     // meta ファイルの処理
-    if meta_path.exists() {
+    let mut meta = if check.meta_identical(&meta_path)? {
+        Placement::Identical
+    } else if check.exists(&meta_path) {
         let meta_path_display = PathBuf::from(pathname)
             .parent()
             .unwrap()
             .join(format!("{}.meta", file_name));
 
-        let action = ui_handler.confirm_overwrite(&meta_path_display.display().to_string());
-
-        match action {
-            OverwriteAction::Overwrite => {
-                write_meta_file(&meta_path, asset_meta)?;
+        match check.resolve(&meta_path_display.display().to_string(), OverwriteTarget::Meta) {
+            Some(OverwriteAction::Overwrite) => Placement::Overwrite,
+            Some(OverwriteAction::Rename) => {
+                // assetもmetaに合わせた名前で作成し、コピーには新しいGUIDを割り当てる
+                asset_name = unique_name(parent_dir, file_name, |path| check.exists(path));
+                new_guid = true;
+                Placement::Rename { to: asset_name.clone() }
             }
-            OverwriteAction::Rename => {
-                let new_name = find_unique_name(&output_file_path, file_name);
-                let new_meta_path = output_file_path
-                    .parent()
-                    .unwrap()
-                    .join(format!("{}.meta", new_name));
-                write_meta_file(&new_meta_path, asset_meta)?;
-                asset_rename = Some(new_name);
+            // 既存のmetaを残し、assetはassetの上書きモードに従う
+            Some(OverwriteAction::Skip) => Placement::Skip,
+            None => Placement::Ask,
+        }
+    } else {
+        Placement::Create
+    };
+
+    // 実体ファイルの処理
+    let asset_path = parent_dir.join(&asset_name);
+    let asset = if !check.exists(&asset_path) {
+        Placement::Create
+    } else if asset_name == file_name && check.asset_identical(&asset_path)? {
+        Placement::Identical
+    } else {
+        match check.resolve(&asset_name, OverwriteTarget::Asset) {
+            Some(OverwriteAction::Overwrite) => Placement::Overwrite,
+            Some(OverwriteAction::Rename) => {
+                let new_name = unique_name(parent_dir, &asset_name, |path| check.exists(path));
+
+                // 今回作成する予定だったmetaは一緒にリネームし、GUIDはそのまま使う
+                // 既存のmetaが元の名前に残る場合は、GUIDが重複しないようコピーに新しいGUIDのmetaを作成する
+                if meta == Placement::Create {
+                    meta = Placement::Rename { to: new_name.clone() };
+                } else {
+                    new_guid = true;
+                }
+                Placement::Rename { to: new_name }
             }
-            OverwriteAction::Skip => {
-                skip_asset = true;
-                println!("スキップ: {}", meta_path.display());
+            Some(OverwriteAction::Skip) => Placement::Skip,
+            None => Placement::Ask,
+        }
+    };
+
+    Ok(FilePlacement {
+        meta,
+        asset_name,
+        asset,
+        new_guid,
+    })
+}
+
+/// 展開時の確認（ディスク上の既存のファイルとUIの応答で判定する）
+struct DiskCheck<'a, 'b, U: UiHandler> {
+    ui_handler: &'a mut U,
+    rule_mode: Option<OverwriteMode>,
+    skip_identical: bool,
+    asset_meta: &'a str,
+    source: &'a mut AssetSource<'b>,
+    /// ストリームから読み込んで比較した内容（書き込みに使う）
+    buffered: Option<Vec<u8>>,
+}
+
+impl<U: UiHandler> PlacementCheck for DiskCheck<'_, '_, U> {
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn meta_identical(&mut self, path: &Path) -> Result<bool> {
+        if !self.skip_identical || !path.is_file() {
+            return Ok(false);
+        }
+        Ok(std::fs::read_to_string(path).map_err(Error::io(IoOperation::Read, path))? == self.asset_meta)
+    }
+
+    fn asset_identical(&mut self, path: &Path) -> Result<bool> {
+        if !self.skip_identical || !path.is_file() {
+            return Ok(false);
+        }
+        let identical;
+        (identical, self.buffered) = compare_with_existing(path, self.source)?;
+        Ok(identical)
+    }
+
+    fn resolve(&mut self, path: &str, target: OverwriteTarget) -> Option<OverwriteAction> {
+        Some(confirm_overwrite(self.ui_handler, self.rule_mode, path, target))
+    }
+}
+
+/// assetとmetaを配置する
+/// `options.skip_identical` が有効な場合、既存のファイルと内容が同じmeta・assetは確認せずにそのまま残す
+/// `options.overwrite_policy` の規則にマッチしたpathnameは確認せずに規則のモードに従う
+/// `options.backup_dir` が指定されている場合、上書きする既存のファイルはそこへ移動してから書き込む
+/// リネームしてコピーを作成した場合はその情報を返す
+pub(crate) fn handle_file<U: UiHandler>(
+    output_dir: &Path,
+    pathname: &str,
+    asset_meta: &str,
+    mut source: AssetSource,
+    options: &ExtractOptions,
+    ui_handler: &mut U,
+) -> Result<Option<RenamedCopy>> {
+    let output_file_path = output_dir.join(pathname);
+    let output_basedir = output_file_path.parent().unwrap();
+
+    if !output_basedir.exists() {
+        std::fs::create_dir_all(output_basedir)
+            .map_err(Error::io(IoOperation::CreateDir, output_basedir))?;
+    }
+
+    let mut check = DiskCheck {
+        ui_handler,
+        rule_mode: options.overwrite_policy.mode_for(pathname),
+        skip_identical: options.skip_identical,
+        asset_meta,
+        source: &mut source,
+        buffered: None,
+    };
+    let placement = place_file(&mut check, output_basedir, pathname)?;
+    let buffered = check.buffered.take();
+
+    let file_name = pathname.split('/').next_back().unwrap_or("");
+    let meta_path = output_basedir.join(format!("{}.meta", file_name));
+    let old_guid = read_meta_guid(asset_meta).unwrap_or_default().to_string();
+    let new_guid = placement.new_guid.then(generate_guid);
+    let copy_meta = match &new_guid {
+        Some(guid) => replace_meta_guid(asset_meta, guid),
+        None => asset_meta.to_string(),
+    };
+
+    // meta ファイルの処理
+    match &placement.meta {
+        Placement::Create => write_meta_file(&meta_path, asset_meta)?,
+        Placement::Overwrite => {
+            if let Some(backup_dir) = &options.backup_dir {
+                backup_file(backup_dir, output_dir, &meta_path)?;
             }
+            write_meta_file(&meta_path, asset_meta)?;
         }
-    } else {
-        write_meta_file(&meta_path, asset_meta)?;
+        Placement::Rename { to } => {
+            write_meta_file(&output_basedir.join(format!("{}.meta", to)), &copy_meta)?;
+        }
+        Placement::Skip => eprintln!("スキップ: {}", meta_path.display()),
+        Placement::Identical | Placement::Ask => {}
     }
 
     // 実体ファイルの処理
-    if !skip_asset {
-        let mut final_output_file_path = output_file_path.clone();
-        if let Some(new_name) = asset_rename {
-            final_output_file_path = output_file_path.parent().unwrap().join(new_name);
+    let final_output_file_path = match &placement.asset {
+        Placement::Create => output_basedir.join(&placement.asset_name),
+        Placement::Identical | Placement::Ask => return Ok(None),
+        Placement::Skip => {
+            eprintln!("スキップ: {}", output_basedir.join(&placement.asset_name).display());
+            return Ok(None);
         }
+        Placement::Overwrite => {
+            let path = output_basedir.join(&placement.asset_name);
+            if let Some(backup_dir) = &options.backup_dir {
+                backup_file(backup_dir, output_dir, &path)?;
+            }
+            path
+        }
+        Placement::Rename { to } => {
+            if placement.meta != (Placement::Rename { to: to.clone() }) {
+                write_meta_file(&output_basedir.join(format!("{}.meta", to)), &copy_meta)?;
+            }
+            output_basedir.join(to)
+        }
+    };
 
-        if final_output_file_path.exists() {
-            let action = ui_handler.confirm_overwrite(
-                &final_output_file_path.file_name().unwrap().to_string_lossy()
-            );
+    match (source, buffered) {
+        (_, Some(content)) => {
+            std::fs::write(&final_output_file_path, content)
+                .map_err(Error::io(IoOperation::Write, &final_output_file_path))?;
+        }
+        (AssetSource::File(source_file_path), None) => {
+            std::fs::rename(source_file_path, &final_output_file_path)
+                .map_err(Error::io(IoOperation::Rename, &final_output_file_path))?;
+        }
+        (AssetSource::Reader(reader, _), None) => {
+            let mut output_file = File::create(&final_output_file_path)
+                .map_err(Error::io(IoOperation::Create, &final_output_file_path))?;
+            std::io::copy(reader, &mut output_file)
+                .map_err(Error::io(IoOperation::Write, &final_output_file_path))?;
+        }
+        (AssetSource::Data(data), None) => {
+            std::fs::write(&final_output_file_path, data)
+                .map_err(Error::io(IoOperation::Write, &final_output_file_path))?;
+        }
+    }
 
-            match action {
-                OverwriteAction::Overwrite => {
-                    // そのまま上書き（既存の挙動）
-                }
-                OverwriteAction::Rename => {
-                    // ユニーク名を生成
-                    let file_name = final_output_file_path.file_name().unwrap().to_string_lossy();
-                    let new_name = find_unique_name(&final_output_file_path, &file_name);
-
-                    // meta fileも既に書き込まれている場合、一緒にリネーム
-                    let old_meta_path = final_output_file_path.parent().unwrap().join(format!("{}.meta", file_name));
-                    if old_meta_path.exists() {
-                        let new_meta_path = final_output_file_path.parent().unwrap().join(format!("{}.meta", new_name));
-                        std::fs::rename(&old_meta_path, &new_meta_path)
-                            .map_err(|e| format!("Failed to rename meta file: {}", e))?;
-                    }
-
-                    // asset fileのパスを更新
-                    final_output_file_path = final_output_file_path.parent().unwrap().join(new_name);
-                }
-                OverwriteAction::Skip => {
-                    println!("スキップ: {}", final_output_file_path.display());
-                    return Ok(());
-                }
-            }
+    if let Some(new_guid) = new_guid {
+        return Ok(Some(RenamedCopy {
+            old_guid,
+            new_guid,
+            asset_path: final_output_file_path,
+        }));
+    }
+
+    Ok(None)
+}
+
+/// 既存のファイルの扱いを決める（ポリシーの規則にマッチした場合は確認しない）
+fn confirm_overwrite<U: UiHandler>(
+    ui_handler: &mut U,
+    rule_mode: Option<OverwriteMode>,
+    path: &str,
+    target: OverwriteTarget,
+) -> OverwriteAction {
+    match rule_mode {
+        Some(OverwriteMode::Overwrite) => OverwriteAction::Overwrite,
+        Some(OverwriteMode::Skip) => OverwriteAction::Skip,
+        Some(OverwriteMode::Rename) => OverwriteAction::Rename,
+        Some(OverwriteMode::Ask) | None => ui_handler.confirm_overwrite(path, target),
+    }
+}
+
+/// 既存のファイルとassetの内容が同じか判定する（サイズが同じ場合だけSHA-256を比較する）
+/// ストリームは読み直せないため、読み込んだ場合はその内容も返す
+fn compare_with_existing(existing: &Path, source: &mut AssetSource) -> Result<(bool, Option<Vec<u8>>)> {
+    let existing_size = std::fs::metadata(existing)
+        .map_err(Error::io(IoOperation::Metadata, existing))?
+        .len();
+    let source_size = match source {
+        AssetSource::File(path) => std::fs::metadata(*path)
+            .map_err(Error::io(IoOperation::Metadata, *path))?
+            .len(),
+        AssetSource::Reader(_, size) => *size,
+        AssetSource::Data(data) => data.len() as u64,
+    };
+    if existing_size != source_size {
+        return Ok((false, None));
+    }
+
+    let existing_hash = file_sha256(existing)?;
+    match source {
+        AssetSource::File(path) => Ok((file_sha256(path)? == existing_hash, None)),
+        AssetSource::Reader(reader, _) => {
+            let mut content = Vec::new();
+            reader
+                .read_to_end(&mut content)
+                .map_err(Error::io(IoOperation::Read, existing))?;
+            Ok((hex_string(&Sha256::digest(&content)) == existing_hash, Some(content)))
         }
+        AssetSource::Data(data) => Ok((hex_string(&Sha256::digest(data)) == existing_hash, None)),
+    }
+}
 
-        std::fs::rename(source_file_path, final_output_file_path)
-            .map_err(|e| format!("Failed to rename source file to output file: {}", e))?;
+/// リネームしたコピー同士の参照を新しいGUIDに書き換える
+/// 同じパッケージからコピーされたアセットが、元のアセットではなくコピーを参照するようにする
+pub(crate) fn rewrite_renamed_references(copies: &[RenamedCopy]) -> Result<()> {
+    let mapping: HashMap<String, String> = copies
+        .iter()
+        .map(|copy| (copy.old_guid.clone(), copy.new_guid.clone()))
+        .collect();
+
+    for copy in copies {
+        // テキスト形式（YAML）のアセットのみ対象にする
+        let Ok(content) = std::fs::read_to_string(&copy.asset_path) else {
+            continue;
+        };
+        if !content.starts_with("%YAML") {
+            continue;
+        }
+        if let Some(rewritten) = rewrite_guid_references(&content, &mapping) {
+            std::fs::write(&copy.asset_path, rewritten)
+                .map_err(Error::io(IoOperation::Write, &copy.asset_path))?;
+        }
     }
 
     Ok(())
 }
 
-fn write_meta_file(path: &Path, content: &str) -> Result<(), String> {
+fn write_meta_file(path: &Path, content: &str) -> Result<()> {
     let mut meta_file = File::create(path)
-        .map_err(|e| format!("metaファイル作成失敗: {}", e))?;
+        .map_err(Error::io(IoOperation::Create, path))?;
     meta_file
         .write_all(content.as_bytes())
-        .map_err(|e| format!("Failed to write file meta: {}", e))?;
+        .map_err(Error::io(IoOperation::Write, path))?;
     Ok(())
 }
 
-fn find_unique_name(base_path: &Path, original_name: &str) -> String {
-    let parent = base_path.parent().unwrap();
+/// `_copyN` を付けて、assetとmetaのどちらとも衝突しない名前を探す
+/// 存在確認は `exists` で行う（ドライランでは計画済みのパスも含めるため）
+pub(crate) fn unique_name(parent: &Path, original_name: &str, exists: impl Fn(&Path) -> bool) -> String {
     let mut count = 1;
 
     loop {
@@ -218,9 +492,204 @@ fn find_unique_name(base_path: &Path, original_name: &str) -> String {
         let asset_path = parent.join(&new_name);
         let meta_path = parent.join(format!("{}.meta", new_name));
 
-        if !asset_path.exists() && !meta_path.exists() {
+        if !exists(&asset_path) && !exists(&meta_path) {
             return new_name;
         }
         count += 1;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{meta, temp_dir};
+    use std::io::Cursor;
+
+    const GUID: &str = "0123456789abcdef0123456789abcdef";
+
+    /// 上書きの確認を記録し、決まった応答を返すハンドラー
+    struct RecordingHandler {
+        meta_action: OverwriteAction,
+        asset_action: OverwriteAction,
+        prompts: Vec<(String, OverwriteTarget)>,
+    }
+
+    impl RecordingHandler {
+        fn new(action: OverwriteAction) -> Self {
+            Self::with_actions(action, action)
+        }
+
+        fn with_actions(meta_action: OverwriteAction, asset_action: OverwriteAction) -> Self {
+            Self {
+                meta_action,
+                asset_action,
+                prompts: Vec::new(),
+            }
+        }
+    }
+
+    impl UiHandler for RecordingHandler {
+        fn update_progress(&mut self, _value: f32, _text: &str) {}
+
+        fn finish(&mut self) {}
+
+        fn confirm_overwrite(&mut self, path: &str, target: OverwriteTarget) -> OverwriteAction {
+            self.prompts.push((path.to_string(), target));
+            match target {
+                OverwriteTarget::Meta => self.meta_action,
+                OverwriteTarget::Asset => self.asset_action,
+            }
+        }
+
+        fn is_cancelled(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn compare_with_existing_checks_size_and_content() {
+        let dir = temp_dir("compare-existing");
+        let existing = dir.join("existing.txt");
+        std::fs::write(&existing, "hello").unwrap();
+        let same = dir.join("same.txt");
+        std::fs::write(&same, "hello").unwrap();
+
+        assert_eq!(compare_with_existing(&existing, &mut AssetSource::File(&same)).unwrap(), (true, None));
+        assert_eq!(compare_with_existing(&existing, &mut AssetSource::Data(b"hello")).unwrap(), (true, None));
+        // サイズが同じでも内容が異なれば別のファイル
+        assert_eq!(compare_with_existing(&existing, &mut AssetSource::Data(b"world")).unwrap(), (false, None));
+        assert_eq!(compare_with_existing(&existing, &mut AssetSource::Data(b"hi")).unwrap(), (false, None));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compare_with_existing_returns_what_it_read_from_a_stream() {
+        let dir = temp_dir("compare-reader");
+        let existing = dir.join("existing.txt");
+        std::fs::write(&existing, "hello").unwrap();
+
+        let mut reader = Cursor::new(b"hello".to_vec());
+        let result = compare_with_existing(&existing, &mut AssetSource::Reader(&mut reader, 5)).unwrap();
+        assert_eq!(result, (true, Some(b"hello".to_vec())));
+
+        let mut reader = Cursor::new(b"world".to_vec());
+        let result = compare_with_existing(&existing, &mut AssetSource::Reader(&mut reader, 5)).unwrap();
+        assert_eq!(result, (false, Some(b"world".to_vec())));
+
+        // サイズが異なる場合はストリームを読まない
+        let mut reader = Cursor::new(b"hi".to_vec());
+        let result = compare_with_existing(&existing, &mut AssetSource::Reader(&mut reader, 2)).unwrap();
+        assert_eq!(result, (false, None));
+        assert_eq!(reader.position(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn handle_file_leaves_identical_meta_and_asset_without_prompting() {
+        let dir = temp_dir("handle-identical");
+        std::fs::write(dir.join("a.txt"), "hello").unwrap();
+        std::fs::write(dir.join("a.txt.meta"), meta(GUID)).unwrap();
+        let options = ExtractOptions {
+            skip_identical: true,
+            ..ExtractOptions::default()
+        };
+
+        let mut handler = RecordingHandler::new(OverwriteAction::Rename);
+        let copy = handle_file(&dir, "a.txt", &meta(GUID), AssetSource::Data(b"hello"), &options, &mut handler).unwrap();
+        assert_eq!(copy, None);
+        assert_eq!(handler.prompts, []);
+        assert!(!dir.join("a_copy1.txt").exists());
+
+        // 無効な場合は内容が同じでも上書きモードに従う
+        let mut handler = RecordingHandler::new(OverwriteAction::Skip);
+        let options = ExtractOptions::default();
+        handle_file(&dir, "a.txt", &meta(GUID), AssetSource::Data(b"hello"), &options, &mut handler).unwrap();
+        assert_eq!(
+            handler.prompts,
+            [("a.txt.meta".to_string(), OverwriteTarget::Meta), ("a.txt".to_string(), OverwriteTarget::Asset)]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn handle_file_writes_the_stream_it_compared() {
+        let dir = temp_dir("handle-reader");
+        std::fs::write(dir.join("a.txt"), "hello").unwrap();
+        std::fs::write(dir.join("a.txt.meta"), meta(GUID)).unwrap();
+        let options = ExtractOptions {
+            skip_identical: true,
+            ..ExtractOptions::default()
+        };
+
+        let mut reader = Cursor::new(b"world".to_vec());
+        let mut handler = RecordingHandler::new(OverwriteAction::Overwrite);
+        handle_file(&dir, "a.txt", &meta(GUID), AssetSource::Reader(&mut reader, 5), &options, &mut handler).unwrap();
+        assert_eq!(handler.prompts, [("a.txt".to_string(), OverwriteTarget::Asset)]);
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "world");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn asset_rename_keeps_the_guid_unless_a_meta_stays_behind() {
+        // 既存のassetにmetaがない場合は、作成するmetaごとリネームしてGUIDを変えない
+        let dir = temp_dir("rename-asset-guid");
+        std::fs::write(dir.join("a.txt"), "existing").unwrap();
+        let mut handler = RecordingHandler::new(OverwriteAction::Rename);
+        let options = ExtractOptions::default();
+        let copy = handle_file(&dir, "a.txt", &meta(GUID), AssetSource::Data(b"package"), &options, &mut handler).unwrap();
+        assert_eq!(copy, None);
+        assert!(!dir.join("a.txt.meta").exists());
+        assert_eq!(std::fs::read_to_string(dir.join("a_copy1.txt.meta")).unwrap(), meta(GUID));
+        assert_eq!(std::fs::read_to_string(dir.join("a_copy1.txt")).unwrap(), "package");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // 既存のmetaを残した場合はコピーに新しいGUIDを割り当てる
+        let dir = temp_dir("rename-asset-new-guid");
+        std::fs::write(dir.join("a.txt"), "existing").unwrap();
+        std::fs::write(dir.join("a.txt.meta"), meta(GUID)).unwrap();
+        let mut handler = RecordingHandler::with_actions(OverwriteAction::Skip, OverwriteAction::Rename);
+        let copy = handle_file(&dir, "a.txt", &meta(GUID), AssetSource::Data(b"package"), &options, &mut handler)
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.old_guid, GUID);
+        assert_ne!(copy.new_guid, GUID);
+        assert_eq!(copy.asset_path, dir.join("a_copy1.txt"));
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt.meta")).unwrap(), meta(GUID));
+        assert_eq!(std::fs::read_to_string(dir.join("a_copy1.txt.meta")).unwrap(), meta(&copy.new_guid));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn meta_and_asset_follow_their_own_modes() {
+        let old_meta = format!("{}userData: old\n", meta(GUID));
+        let options = ExtractOptions::default();
+
+        // metaはスキップし、assetだけを上書きする
+        let dir = temp_dir("modes-meta-skip");
+        std::fs::write(dir.join("a.txt"), "existing").unwrap();
+        std::fs::write(dir.join("a.txt.meta"), &old_meta).unwrap();
+        let mut handler = RecordingHandler::with_actions(OverwriteAction::Skip, OverwriteAction::Overwrite);
+        let copy = handle_file(&dir, "a.txt", &meta(GUID), AssetSource::Data(b"package"), &options, &mut handler).unwrap();
+        assert_eq!(copy, None);
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt.meta")).unwrap(), old_meta);
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "package");
+        assert!(!dir.join("a_copy1.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // metaだけを上書きし、assetはスキップする
+        let dir = temp_dir("modes-asset-skip");
+        std::fs::write(dir.join("a.txt"), "existing").unwrap();
+        std::fs::write(dir.join("a.txt.meta"), &old_meta).unwrap();
+        let mut handler = RecordingHandler::with_actions(OverwriteAction::Overwrite, OverwriteAction::Skip);
+        let copy = handle_file(&dir, "a.txt", &meta(GUID), AssetSource::Data(b"package"), &options, &mut handler).unwrap();
+        assert_eq!(copy, None);
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt.meta")).unwrap(), meta(GUID));
+        assert_eq!(std::fs::read_to_string(dir.join("a.txt")).unwrap(), "existing");
+        assert!(!dir.join("a_copy1.txt").exists());
+        assert_eq!(
+            handler.prompts,
+            [("a.txt.meta".to_string(), OverwriteTarget::Meta), ("a.txt".to_string(), OverwriteTarget::Asset)]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
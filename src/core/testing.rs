@@ -0,0 +1,40 @@
+//! テスト用の一時ディレクトリとパッケージの作成
+
+use crate::core::extract::{ASSET_FILE_NAME, ASSET_META_FILENAME, PATHNAME_FILENAME};
+use crate::core::writer::PackageWriter;
+use std::path::{Path, PathBuf};
+
+/// テストごとの空の一時ディレクトリ
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("unitypackage-extractor-{}-{}", name, std::process::id()));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// GUIDだけを書いたmeta
+pub(crate) fn meta(guid: &str) -> String {
+    format!("fileFormatVersion: 2\nguid: {}\n", guid)
+}
+
+/// `(GUID, pathname, asset)` のエントリを書き込んだパッケージを作成する
+/// assetが `None` のエントリはフォルダのmetaを書き込む
+pub(crate) fn write_package(path: &Path, entries: &[(&str, &str, Option<&[u8]>)]) {
+    let mut writer = PackageWriter::create(path).unwrap();
+    for (guid, pathname, asset) in entries {
+        writer.append_data(guid, PATHNAME_FILENAME, pathname.as_bytes()).unwrap();
+        match asset {
+            Some(asset) => {
+                writer.append_data(guid, ASSET_META_FILENAME, meta(guid).as_bytes()).unwrap();
+                writer.append_data(guid, ASSET_FILE_NAME, asset).unwrap();
+            }
+            None => {
+                let folder_meta = format!("{}folderAsset: yes\n", meta(guid));
+                writer.append_data(guid, ASSET_META_FILENAME, folder_meta.as_bytes()).unwrap();
+            }
+        }
+    }
+    writer.finish().unwrap();
+}
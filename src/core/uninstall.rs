@@ -0,0 +1,167 @@
+use crate::core::entry::EntryKind;
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::guid_index::GuidIndex;
+use crate::core::list::{file_sha256, ListEntry};
+use std::path::Path;
+
+/// アンインストールの結果（パスはプロジェクトからの相対パス）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UninstallSummary {
+    /// 削除したアセットとフォルダ（metaも一緒に削除）
+    pub removed: Vec<String>,
+    /// 展開後に内容が変更されているため残したアセット
+    pub modified: Vec<String>,
+    /// 空でないため残したフォルダ
+    pub kept_folders: Vec<String>,
+    /// assetが見つからないため、内容を確認できずに残したmeta（assetのパス）
+    pub missing_assets: Vec<String>,
+    /// 種類（フォルダかファイルか）を判定できないため対象外にしたエントリ（pathname、なければGUID）
+    pub unknown_kind: Vec<String>,
+}
+
+/// パッケージで展開したアセットをプロジェクトから削除する
+///
+/// プロジェクトの既存のmetaを走査し、パッケージのエントリと同じGUIDのアセットを探す。
+/// ユーザーが移動したアセットもGUIDで見つかる。assetの内容（SHA-256）がパッケージと同じ場合だけ
+/// assetとmetaを削除し、変更されているものは残す。assetが見つからないファイルのmetaも確認できないため残す。
+/// フォルダは中身がすべて削除されて空になった場合だけ削除する。
+/// リネームしたコピーや `--remap-guids` で展開したアセットはGUIDが異なるため対象外。
+pub fn uninstall_package(entries: &[ListEntry], project_dir: &Path) -> Result<UninstallSummary> {
+    let index = GuidIndex::scan_for_pathnames(project_dir, entries.iter().filter_map(|entry| entry.pathname.as_deref()))?;
+    let mut summary = UninstallSummary::default();
+
+    let mut folders: Vec<&String> = Vec::new();
+    for entry in entries {
+        match entry.kind {
+            Some(EntryKind::Folder) => {
+                folders.extend(index.paths_for(&entry.guid));
+                continue;
+            }
+            Some(EntryKind::File) => {}
+            None => {
+                summary
+                    .unknown_kind
+                    .push(entry.pathname.clone().unwrap_or_else(|| entry.guid.clone()));
+                continue;
+            }
+        }
+
+        for pathname in index.paths_for(&entry.guid) {
+            let asset_path = project_dir.join(pathname);
+            let meta_path = project_dir.join(format!("{}.meta", pathname));
+            if asset_path.is_dir() {
+                continue;
+            }
+            if !asset_path.is_file() {
+                summary.missing_assets.push(pathname.clone());
+                continue;
+            }
+            if entry.sha256.is_none() || entry.sha256 != Some(file_sha256(&asset_path)?) {
+                summary.modified.push(pathname.clone());
+                continue;
+            }
+            std::fs::remove_file(&asset_path).map_err(Error::io(IoOperation::Remove, &asset_path))?;
+            std::fs::remove_file(&meta_path).map_err(Error::io(IoOperation::Remove, &meta_path))?;
+            summary.removed.push(pathname.clone());
+        }
+    }
+
+    // 中身から先に削除できるよう、深い階層のフォルダから処理する
+    folders.sort_by(|a, b| b.split('/').count().cmp(&a.split('/').count()).then_with(|| a.cmp(b)));
+    for pathname in folders {
+        let folder_path = project_dir.join(pathname);
+        let meta_path = project_dir.join(format!("{}.meta", pathname));
+        if folder_path.is_dir() {
+            let mut read_dir =
+                std::fs::read_dir(&folder_path).map_err(Error::io(IoOperation::ReadDir, &folder_path))?;
+            if read_dir.next().is_some() {
+                summary.kept_folders.push(pathname.clone());
+                continue;
+            }
+            std::fs::remove_dir(&folder_path).map_err(Error::io(IoOperation::Remove, &folder_path))?;
+        } else if folder_path.exists() {
+            continue;
+        }
+        std::fs::remove_file(&meta_path).map_err(Error::io(IoOperation::Remove, &meta_path))?;
+        summary.removed.push(format!("{}/", pathname));
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::list::list_entries;
+    use std::path::PathBuf;
+    use crate::core::testing::{meta, temp_dir, write_package};
+
+    const FOLDER: &str = "00000000000000000000000000000001";
+    const FILE: &str = "00000000000000000000000000000002";
+
+    /// フォルダ `Assets/Foo` とファイル `Assets/Foo/a.txt` を展開した状態のプロジェクト
+    fn installed(name: &str) -> (PathBuf, PathBuf, Vec<ListEntry>) {
+        let dir = temp_dir(name);
+        let package = dir.join("package.unitypackage");
+        write_package(&package, &[(FOLDER, "Assets/Foo", None), (FILE, "Assets/Foo/a.txt", Some(b"package"))]);
+
+        let project = dir.join("proj");
+        std::fs::create_dir_all(project.join("Assets/Foo")).unwrap();
+        std::fs::write(project.join("Assets/Foo.meta"), format!("{}folderAsset: yes\n", meta(FOLDER))).unwrap();
+        std::fs::write(project.join("Assets/Foo/a.txt"), "package").unwrap();
+        std::fs::write(project.join("Assets/Foo/a.txt.meta"), meta(FILE)).unwrap();
+        (dir, project, list_entries(&package).unwrap())
+    }
+
+    #[test]
+    fn removes_unchanged_files_and_emptied_folders() {
+        let (dir, project, entries) = installed("uninstall-unchanged");
+        let summary = uninstall_package(&entries, &project).unwrap();
+
+        assert_eq!(summary.removed, ["Assets/Foo/a.txt", "Assets/Foo/"]);
+        assert!(!project.join("Assets/Foo").exists());
+        assert!(!project.join("Assets/Foo.meta").exists());
+        assert!(project.join("Assets").is_dir());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_modified_files_and_their_folders() {
+        let (dir, project, entries) = installed("uninstall-modified");
+        std::fs::write(project.join("Assets/Foo/a.txt"), "edited").unwrap();
+        let summary = uninstall_package(&entries, &project).unwrap();
+
+        assert_eq!(summary.removed, Vec::<String>::new());
+        assert_eq!(summary.modified, ["Assets/Foo/a.txt"]);
+        assert_eq!(summary.kept_folders, ["Assets/Foo"]);
+        assert!(project.join("Assets/Foo/a.txt.meta").is_file());
+        assert!(project.join("Assets/Foo.meta").is_file());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_folders_with_other_files() {
+        let (dir, project, entries) = installed("uninstall-nonempty");
+        std::fs::write(project.join("Assets/Foo/mine.txt"), "user file").unwrap();
+        let summary = uninstall_package(&entries, &project).unwrap();
+
+        assert_eq!(summary.removed, ["Assets/Foo/a.txt"]);
+        assert_eq!(summary.kept_folders, ["Assets/Foo"]);
+        assert!(project.join("Assets/Foo/mine.txt").is_file());
+        assert!(project.join("Assets/Foo.meta").is_file());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_meta_when_asset_is_missing() {
+        let (dir, project, entries) = installed("uninstall-missing");
+        std::fs::remove_file(project.join("Assets/Foo/a.txt")).unwrap();
+        let summary = uninstall_package(&entries, &project).unwrap();
+
+        assert_eq!(summary.missing_assets, ["Assets/Foo/a.txt"]);
+        assert_eq!(summary.removed, Vec::<String>::new());
+        assert!(project.join("Assets/Foo/a.txt.meta").is_file());
+        assert_eq!(summary.kept_folders, ["Assets/Foo"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
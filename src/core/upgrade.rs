@@ -0,0 +1,157 @@
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::guid_index::GuidIndex;
+use crate::core::path::validate_pathname;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// アップグレード時に、同じGUIDの既存のアセットをパッケージのpathnameへ移動する操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpgradeMove {
+    pub guid: String,
+    /// 移動元（出力先ディレクトリからの相対パス）
+    pub from: String,
+    /// 移動先（パッケージのpathname）
+    pub to: String,
+    pub is_folder: bool,
+}
+
+/// パッケージのエントリ（GUID, pathname）と同じGUIDのアセットが別のパスにある場合の移動を判定する
+///
+/// Unityのインポートと同様に、既存のアセットをパッケージのpathnameへ移動してから上書きする。
+/// 移動先に既にファイルがある場合や、同じGUIDが複数の場所にある場合は移動しない。
+/// 出力先の外を指すなど安全でないpathname（`--allow-unsafe-paths` でも）へは移動しない。
+/// 親フォルダを先に移動するため、pathnameの階層順に判定し、移動済みのフォルダの中身はそのパスで扱う。
+pub fn plan_upgrade(entries: &[(&str, &str)], project_dir: &Path) -> Result<Vec<UpgradeMove>> {
+    let index = GuidIndex::scan_for_pathnames(project_dir, entries.iter().map(|(_, pathname)| *pathname))?;
+
+    let mut sorted: Vec<(&str, &str)> = entries
+        .iter()
+        .copied()
+        .filter(|(_, pathname)| validate_pathname(pathname).is_ok())
+        .collect();
+    sorted.sort_by(|a, b| a.1.split('/').cmp(b.1.split('/')));
+
+    let mut moves: Vec<UpgradeMove> = Vec::new();
+    let mut occupied: HashSet<String> = HashSet::new();
+    for (guid, pathname) in sorted {
+        let existing_paths = index.paths_for(guid);
+        if existing_paths.len() != 1 || existing_paths[0] == pathname {
+            continue;
+        }
+
+        // 先に移動したフォルダの中にある場合は移動後のパスにする
+        let from = path_after_upgrade(&existing_paths[0], &moves);
+        if from == pathname || is_descendant(pathname, &from) {
+            continue;
+        }
+
+        // 判定は先に予定した移動を反映した状態で行う
+        let is_folder = current_path(project_dir, project_dir.join(&from), &moves).is_dir();
+        let target = current_path(project_dir, project_dir.join(pathname), &moves);
+        let target_meta = current_path(project_dir, project_dir.join(format!("{}.meta", pathname)), &moves);
+        if occupied.contains(pathname) || target.exists() || target_meta.exists() {
+            continue;
+        }
+
+        occupied.insert(pathname.to_string());
+        moves.push(UpgradeMove {
+            guid: guid.to_string(),
+            from,
+            to: pathname.to_string(),
+            is_folder,
+        });
+    }
+
+    Ok(moves)
+}
+
+/// 移動を実行する（assetとmetaを一緒に移動）
+pub fn apply_upgrade(project_dir: &Path, moves: &[UpgradeMove]) -> Result<()> {
+    for upgrade_move in moves {
+        let from = project_dir.join(&upgrade_move.from);
+        let to = project_dir.join(&upgrade_move.to);
+
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent).map_err(Error::io(IoOperation::CreateDir, parent))?;
+        }
+        if from.exists() {
+            std::fs::rename(&from, &to).map_err(Error::io(IoOperation::Rename, &from))?;
+        }
+
+        let from_meta = project_dir.join(format!("{}.meta", upgrade_move.from));
+        let to_meta = project_dir.join(format!("{}.meta", upgrade_move.to));
+        if from_meta.exists() {
+            std::fs::rename(&from_meta, &to_meta).map_err(Error::io(IoOperation::Rename, &from_meta))?;
+        }
+    }
+    Ok(())
+}
+
+/// 移動後のパスにあるファイルの、移動前の場所（移動対象でなければNone）
+/// ドライランで移動後の状態を判定するために使用する
+pub fn path_before_upgrade(project_dir: &Path, path: &Path, moves: &[UpgradeMove]) -> Option<PathBuf> {
+    let mut relative = path.strip_prefix(project_dir).ok()?.to_str()?.replace('\\', "/");
+    let mut moved = false;
+    // 後の移動ほど先に移動したフォルダの中にあるため、逆順にたどる
+    for upgrade_move in moves.iter().rev() {
+        if relative == upgrade_move.to
+            || relative == format!("{}.meta", upgrade_move.to)
+            || (upgrade_move.is_folder && is_descendant(&relative, &upgrade_move.to))
+        {
+            relative = format!("{}{}", upgrade_move.from, &relative[upgrade_move.to.len()..]);
+            moved = true;
+        }
+    }
+    moved.then(|| project_dir.join(relative))
+}
+
+/// 移動前のパス（出力先ディレクトリからの相対パス）の、すべての移動後の場所
+pub fn path_after_upgrade(path: &str, moves: &[UpgradeMove]) -> String {
+    let mut path = path.to_string();
+    for upgrade_move in moves {
+        if path == upgrade_move.from {
+            path = upgrade_move.to.clone();
+        } else if upgrade_move.is_folder && is_descendant(&path, &upgrade_move.from) {
+            path = format!("{}{}", upgrade_move.to, &path[upgrade_move.from.len()..]);
+        }
+    }
+    path
+}
+
+fn current_path(project_dir: &Path, path: PathBuf, moves: &[UpgradeMove]) -> PathBuf {
+    path_before_upgrade(project_dir, &path, moves).unwrap_or(path)
+}
+
+fn is_descendant(path: &str, ancestor: &str) -> bool {
+    path.strip_prefix(ancestor).is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUID: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn plan_upgrade_skips_unsafe_targets() {
+        let dir = std::env::temp_dir().join(format!("unitypackage-extractor-upgrade-plan-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Assets")).unwrap();
+        std::fs::write(dir.join("Assets/a.txt"), "existing").unwrap();
+        std::fs::write(dir.join("Assets/a.txt.meta"), format!("guid: {}\n", GUID)).unwrap();
+
+        let unsafe_moves = plan_upgrade(&[(GUID, "Assets/../../escaped.txt")], &dir);
+        let safe_moves = plan_upgrade(&[(GUID, "Assets/b.txt")], &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(unsafe_moves.unwrap(), []);
+        assert_eq!(
+            safe_moves.unwrap(),
+            [UpgradeMove {
+                guid: GUID.to_string(),
+                from: "Assets/a.txt".to_string(),
+                to: "Assets/b.txt".to_string(),
+                is_folder: false,
+            }]
+        );
+    }
+}
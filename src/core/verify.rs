@@ -0,0 +1,348 @@
+use crate::core::entry::is_folder_meta;
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::extract::{ASSET_FILE_NAME, ASSET_META_FILENAME, PATHNAME_FILENAME, PREVIEW_FILENAME};
+use crate::core::guid::is_valid_guid;
+use crate::core::guid_index::read_meta_guid;
+use crate::core::path::{archive_entry_depth, split_archive_entry_path, validate_pathname};
+use flate2::read::GzDecoder;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use tar::Archive;
+
+/// パッケージの検証で見つかった問題の種類
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyIssueKind {
+    /// アーカイブ内のエントリが `<GUID>/<ファイル名>` の形式ではない
+    MalformedEntry { path: String },
+    /// GUIDが小文字の16進数32桁ではない
+    InvalidGuid,
+    /// GUIDフォルダにpathnameがない
+    MissingPathname,
+    /// pathnameが出力先の外を指している（絶対パスや `..` を含む）
+    UnsafePathname { reason: String },
+    /// GUIDフォルダに未知のファイルがある
+    UnknownFile { file_name: String },
+    /// GUIDフォルダにasset.metaがない
+    MissingMeta,
+    /// asset.metaに `guid:` がない
+    MetaGuidMissing,
+    /// asset.metaの `guid:` がフォルダ名と異なる
+    MetaGuidMismatch { meta_guid: String },
+    /// asset.metaをYAMLとして読み込めない
+    MetaParse { reason: String },
+    /// 同じpathnameのエントリが複数ある
+    DuplicatePathname { other_guids: Vec<String> },
+    /// フォルダのmetaなのにassetがある
+    FolderHasAsset,
+    /// ファイルのmetaなのにassetがない
+    MissingAsset,
+}
+
+/// パッケージの検証で見つかった問題
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyIssue {
+    /// 問題のあるGUIDフォルダ（アーカイブの構造の問題の場合はNone）
+    pub guid: Option<String>,
+    pub pathname: Option<String>,
+    pub kind: VerifyIssueKind,
+}
+
+impl fmt::Display for VerifyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.guid, &self.pathname) {
+            (Some(guid), Some(pathname)) => write!(f, "{} [{}]: ", pathname, guid)?,
+            (Some(guid), None) => write!(f, "[{}]: ", guid)?,
+            _ => {}
+        }
+        match &self.kind {
+            VerifyIssueKind::MalformedEntry { path } => {
+                write!(f, "エントリは<GUID>/<ファイル名>の形式である必要があります: {}", path)
+            }
+            VerifyIssueKind::InvalidGuid => f.write_str("GUIDが小文字の16進数32桁ではありません"),
+            VerifyIssueKind::MissingPathname => f.write_str("pathnameが見つかりません"),
+            VerifyIssueKind::UnsafePathname { reason } => {
+                write!(f, "出力先の外を指す安全でないpathnameです（{}）", reason)
+            }
+            VerifyIssueKind::UnknownFile { file_name } => write!(f, "未知のファイルが含まれています: {}", file_name),
+            VerifyIssueKind::MissingMeta => f.write_str("asset.metaが見つかりません"),
+            VerifyIssueKind::MetaGuidMissing => f.write_str("asset.metaにguidフィールドが見つかりません"),
+            VerifyIssueKind::MetaGuidMismatch { meta_guid } => {
+                write!(f, "asset.metaのguid（{}）がフォルダ名と一致しません", meta_guid)
+            }
+            VerifyIssueKind::MetaParse { reason } => write!(f, "asset.metaを読み込めません: {}", reason),
+            VerifyIssueKind::DuplicatePathname { other_guids } => {
+                write!(f, "同じpathnameのエントリがあります: {}", other_guids.join(", "))
+            }
+            VerifyIssueKind::FolderHasAsset => f.write_str("metaはフォルダを示していますがassetが含まれています"),
+            VerifyIssueKind::MissingAsset => {
+                f.write_str("assetが含まれておらず、metaにfolderAsset: yesもありません")
+            }
+        }
+    }
+}
+
+/// 検証のために集めるGUIDフォルダごとの情報
+#[derive(Default)]
+struct VerifyEntry {
+    pathname: Option<String>,
+    meta: Option<String>,
+    has_asset: bool,
+    /// pathname・asset・asset.meta・preview.png以外のファイル
+    unknown_files: Vec<String>,
+}
+
+/// パッケージを展開せずに構造を検証し、見つかった問題をすべて返す
+/// 最初の問題で中止せず、アップロード前のチェックなどで一覧できるようにする
+pub fn verify_package(archive_path: &Path) -> Result<Vec<VerifyIssue>> {
+    let file = File::open(archive_path).map_err(Error::io(IoOperation::Open, archive_path))?;
+    let gz = GzDecoder::new(BufReader::new(file));
+    let mut archive = Archive::new(gz);
+
+    let mut issues = Vec::new();
+    let mut entries: BTreeMap<String, VerifyEntry> = BTreeMap::new();
+
+    for entry in archive
+        .entries()
+        .map_err(Error::archive(archive_path))?
+    {
+        let mut entry = entry.map_err(Error::archive(archive_path))?;
+        let path = entry
+            .path()
+            .map_err(Error::archive(archive_path))?
+            .to_path_buf();
+
+        // GUIDフォルダ自体のエントリ
+        if archive_entry_depth(&path) < 2 {
+            if let Some(guid) = path.file_name() {
+                entries.entry(guid.to_string_lossy().to_string()).or_default();
+            }
+            continue;
+        }
+        // GUIDフォルダ内のディレクトリなど、ファイル以外のエントリは読み飛ばす
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let Some((guid, file_name)) = split_archive_entry_path(&path) else {
+            issues.push(VerifyIssue {
+                guid: None,
+                pathname: None,
+                kind: VerifyIssueKind::MalformedEntry {
+                    path: path.to_string_lossy().to_string(),
+                },
+            });
+            continue;
+        };
+        let state = entries.entry(guid).or_default();
+
+        match file_name.as_str() {
+            PATHNAME_FILENAME | ASSET_META_FILENAME => {
+                let mut content = String::new();
+                entry
+                    .read_to_string(&mut content)
+                    .map_err(Error::archive(archive_path))?;
+                if file_name == PATHNAME_FILENAME {
+                    state.pathname = Some(content);
+                } else {
+                    state.meta = Some(content);
+                }
+            }
+            ASSET_FILE_NAME => state.has_asset = true,
+            PREVIEW_FILENAME => {}
+            _ => state.unknown_files.push(file_name),
+        }
+    }
+
+    let mut guids_by_pathname: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (guid, state) in &entries {
+        if let Some(pathname) = &state.pathname {
+            guids_by_pathname.entry(pathname).or_default().push(guid);
+        }
+    }
+
+    for (guid, state) in &entries {
+        let mut push = |kind| {
+            issues.push(VerifyIssue {
+                guid: Some(guid.clone()),
+                pathname: state.pathname.clone(),
+                kind,
+            })
+        };
+
+        if !is_valid_guid(guid) {
+            push(VerifyIssueKind::InvalidGuid);
+        }
+        for file_name in &state.unknown_files {
+            push(VerifyIssueKind::UnknownFile {
+                file_name: file_name.clone(),
+            });
+        }
+        let Some(pathname) = &state.pathname else {
+            push(VerifyIssueKind::MissingPathname);
+            continue;
+        };
+        if let Err(reason) = validate_pathname(pathname) {
+            push(VerifyIssueKind::UnsafePathname { reason });
+        }
+
+        let other_guids: Vec<String> = guids_by_pathname[pathname.as_str()]
+            .iter()
+            .filter(|other| *other != guid)
+            .map(|other| other.to_string())
+            .collect();
+        if !other_guids.is_empty() {
+            push(VerifyIssueKind::DuplicatePathname { other_guids });
+        }
+
+        let Some(meta) = &state.meta else {
+            push(VerifyIssueKind::MissingMeta);
+            continue;
+        };
+        match read_meta_guid(meta) {
+            None => push(VerifyIssueKind::MetaGuidMissing),
+            Some(meta_guid) if meta_guid != guid => push(VerifyIssueKind::MetaGuidMismatch {
+                meta_guid: meta_guid.to_string(),
+            }),
+            Some(_) => {}
+        }
+        match is_folder_meta(pathname, meta) {
+            Ok(true) if state.has_asset => push(VerifyIssueKind::FolderHasAsset),
+            Ok(false) if !state.has_asset => push(VerifyIssueKind::MissingAsset),
+            Ok(_) => {}
+            Err(Error::MetaParse { reason, .. }) => push(VerifyIssueKind::MetaParse { reason }),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{meta, temp_dir};
+    use crate::core::writer::PackageWriter;
+
+    const GUID: &str = "0123456789abcdef0123456789abcdef";
+    const OTHER: &str = "fedcba9876543210fedcba9876543210";
+
+    /// `(GUID, ファイル名, 内容)` をそのまま書き込んだパッケージを検証する
+    fn verify(name: &str, files: &[(&str, &str, &[u8])]) -> Vec<VerifyIssue> {
+        let dir = temp_dir(name);
+        let package = dir.join("package.unitypackage");
+        let mut writer = PackageWriter::create(&package).unwrap();
+        for (guid, file_name, data) in files {
+            writer.append_data(guid, file_name, data).unwrap();
+        }
+        writer.finish().unwrap();
+        let issues = verify_package(&package).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        issues
+    }
+
+    fn kinds(issues: &[VerifyIssue]) -> Vec<&VerifyIssueKind> {
+        issues.iter().map(|issue| &issue.kind).collect()
+    }
+
+    #[test]
+    fn valid_package_has_no_issues() {
+        let meta = meta(GUID);
+        let issues = verify(
+            "verify-valid",
+            &[
+                (GUID, PATHNAME_FILENAME, b"Assets/a.txt"),
+                (GUID, ASSET_META_FILENAME, meta.as_bytes()),
+                (GUID, ASSET_FILE_NAME, b"a"),
+                (GUID, PREVIEW_FILENAME, b"png"),
+            ],
+        );
+        assert_eq!(issues, []);
+    }
+
+    #[test]
+    fn reports_missing_pathname() {
+        let meta = meta(GUID);
+        let issues = verify(
+            "verify-pathname",
+            &[(GUID, ASSET_META_FILENAME, meta.as_bytes()), (GUID, ASSET_FILE_NAME, b"a")],
+        );
+        assert_eq!(
+            issues,
+            [VerifyIssue {
+                guid: Some(GUID.to_string()),
+                pathname: None,
+                kind: VerifyIssueKind::MissingPathname,
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_missing_meta() {
+        let issues = verify(
+            "verify-meta",
+            &[(GUID, PATHNAME_FILENAME, b"Assets/a.txt"), (GUID, ASSET_FILE_NAME, b"a")],
+        );
+        assert_eq!(kinds(&issues), [&VerifyIssueKind::MissingMeta]);
+        assert_eq!(issues[0].pathname.as_deref(), Some("Assets/a.txt"));
+    }
+
+    #[test]
+    fn reports_meta_guid_mismatch() {
+        let meta = meta(OTHER);
+        let issues = verify(
+            "verify-mismatch",
+            &[
+                (GUID, PATHNAME_FILENAME, b"Assets/a.txt"),
+                (GUID, ASSET_META_FILENAME, meta.as_bytes()),
+                (GUID, ASSET_FILE_NAME, b"a"),
+            ],
+        );
+        assert_eq!(
+            kinds(&issues),
+            [&VerifyIssueKind::MetaGuidMismatch {
+                meta_guid: OTHER.to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_unsafe_pathname() {
+        let meta = meta(GUID);
+        let issues = verify(
+            "verify-unsafe",
+            &[
+                (GUID, PATHNAME_FILENAME, b"../escape.txt"),
+                (GUID, ASSET_META_FILENAME, meta.as_bytes()),
+                (GUID, ASSET_FILE_NAME, b"a"),
+            ],
+        );
+        assert_eq!(issues.len(), 1);
+        assert!(
+            matches!(issues[0].kind, VerifyIssueKind::UnsafePathname { .. }),
+            "{:?}",
+            issues
+        );
+    }
+
+    #[test]
+    fn reports_unknown_file() {
+        let meta = meta(GUID);
+        let issues = verify(
+            "verify-unknown",
+            &[
+                (GUID, PATHNAME_FILENAME, b"Assets/a.txt"),
+                (GUID, ASSET_META_FILENAME, meta.as_bytes()),
+                (GUID, ASSET_FILE_NAME, b"a"),
+                (GUID, "asset.bak", b"old"),
+            ],
+        );
+        assert_eq!(
+            kinds(&issues),
+            [&VerifyIssueKind::UnknownFile {
+                file_name: "asset.bak".to_string()
+            }]
+        );
+    }
+}
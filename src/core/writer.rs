@@ -0,0 +1,91 @@
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::extract::{ASSET_FILE_NAME, ASSET_META_FILENAME, PATHNAME_FILENAME};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tar::Builder;
+
+/// .unitypackageファイルの書き出し
+///
+/// エントリごとに `<GUID>/pathname`、`<GUID>/asset.meta`、`<GUID>/asset` を書き込む。
+/// 最後に必ず `finish` を呼ぶこと。
+pub struct PackageWriter {
+    builder: Builder<GzEncoder<File>>,
+    path: PathBuf,
+}
+
+impl PackageWriter {
+    /// 出力ファイルを作成
+    pub fn create(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = File::create(&path).map_err(Error::io(IoOperation::Create, &path))?;
+        let gz_encoder = GzEncoder::new(file, Compression::default());
+        Ok(Self {
+            builder: Builder::new(gz_encoder),
+            path,
+        })
+    }
+
+    /// 出力ファイルのパス
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// エントリを追加
+    /// `asset_path` が `None` の場合はフォルダとして扱い、assetを書き込まない
+    pub fn append_entry(
+        &mut self,
+        guid: &str,
+        pathname: &str,
+        meta: &str,
+        asset_path: Option<&Path>,
+    ) -> Result<()> {
+        self.append_data(guid, PATHNAME_FILENAME, pathname.as_bytes())?;
+        self.append_data(guid, ASSET_META_FILENAME, meta.as_bytes())?;
+
+        // フォルダーの場合はassetファイルを生成しない
+        if let Some(asset_path) = asset_path {
+            let mut file = File::open(asset_path).map_err(Error::io(IoOperation::Open, asset_path))?;
+            self.builder
+                .append_file(format!("{}/{}", guid, ASSET_FILE_NAME), &mut file)
+                .map_err(Error::io(IoOperation::Write, &self.path))?;
+        }
+
+        Ok(())
+    }
+
+    /// `<GUID>/<ファイル名>` にデータを書き込む
+    pub fn append_data(&mut self, guid: &str, file_name: &str, mut data: &[u8]) -> Result<()> {
+        self.append_reader(guid, file_name, data.len() as u64, &mut data)
+    }
+
+    /// `<GUID>/<ファイル名>` に `size` バイトのデータをストリームから書き込む
+    /// 別のパッケージのエントリをメモリに読み込まずにコピーする場合に使用する
+    pub fn append_reader(&mut self, guid: &str, file_name: &str, size: u64, reader: &mut dyn Read) -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header
+            .set_path(format!("{}/{}", guid, file_name))
+            .map_err(Error::io(IoOperation::Write, &self.path))?;
+        header.set_size(size);
+        header.set_mode(0o644);
+        header.set_cksum();
+
+        self.builder
+            .append(&header, reader)
+            .map_err(Error::io(IoOperation::Write, &self.path))
+    }
+
+    /// アーカイブを完了してファイルを閉じる
+    pub fn finish(self) -> Result<()> {
+        let gz_encoder = self
+            .builder
+            .into_inner()
+            .map_err(Error::io(IoOperation::Write, &self.path))?;
+        gz_encoder
+            .finish()
+            .map_err(Error::io(IoOperation::Write, &self.path))?;
+        Ok(())
+    }
+}
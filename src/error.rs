@@ -0,0 +1,49 @@
+use std::fmt;
+
+/// 実行ファイルのエラー
+/// ライブラリのエラーに加え、コマンドライン引数やGUI版に固有のエラーを含む
+#[derive(Debug)]
+pub enum AppError {
+    /// ライブラリの処理で発生したエラー
+    Library(unitypackage_extractor::Error),
+    /// コマンドライン引数が不正
+    InvalidArgument(String),
+    /// GUI版でサポートされていないコマンド
+    #[cfg(feature = "gui")]
+    UnsupportedInGui { command: &'static str },
+    /// 出力先が選択されなかった
+    #[cfg(feature = "gui")]
+    OutputDirNotSelected,
+}
+
+impl From<unitypackage_extractor::Error> for AppError {
+    fn from(error: unitypackage_extractor::Error) -> Self {
+        AppError::Library(error)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Library(error) => error.fmt(f),
+            AppError::InvalidArgument(message) => f.write_str(message),
+            #[cfg(feature = "gui")]
+            AppError::UnsupportedInGui { command } => write!(
+                f,
+                "GUI版では{}コマンドはサポートされていません。CLI版を使用してください。",
+                command
+            ),
+            #[cfg(feature = "gui")]
+            AppError::OutputDirNotSelected => f.write_str("解凍先フォルダーが選択されませんでした。"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Library(error) => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -1,82 +1,74 @@
 use crate::args::{Args, Command};
-use crate::core::{extract_objects, rebuild_objects};
-use crate::ui::gui::{GuiProgressHandler, ProgressWindow, pick_output_dir};
-use crate::ui::UiHandler;
-use std::collections::HashMap;
+use crate::error::AppError;
+use unitypackage_extractor::{
+    pick_output_dir, Error, ExtractOptions, GuiProgressHandler, OverwriteModes, PackageReader, ProgressWindow, Result,
+};
 use std::sync::{Arc, Mutex};
 use std::path::Path;
 
-const TMP_OUTPUT_DIR: &str = ".jp.ootr.unitypackage-extractor";
-
-pub fn run() -> Result<(), String> {
-    let args = Args::parse()?;
+pub fn run() -> std::result::Result<(), AppError> {
+    let args = Args::parse().map_err(AppError::InvalidArgument)?;
 
     // GUI版は現在extractのみサポート
     match &args.command {
-        Command::Extract { input_file, output_dir, overwrite_mode } => {
-            run_extract(input_file, output_dir.as_ref(), *overwrite_mode)
-        }
-        Command::Compress { .. } => {
-            Err("GUI版ではcompressコマンドはサポートされていません。CLI版を使用してください。".to_string())
+        Command::Extract {
+            input_file,
+            output_dir,
+            overwrite_modes,
+            options,
+            dry_run,
+            ..
+        } => {
+            if *dry_run {
+                return Err(AppError::UnsupportedInGui { command: "extract --dry-run" });
+            }
+            run_extract(input_file, output_dir.as_ref(), *overwrite_modes, options)
         }
+        Command::Compress { .. } => Err(AppError::UnsupportedInGui { command: "compress" }),
+        Command::List { .. } => Err(AppError::UnsupportedInGui { command: "list" }),
+        Command::Verify { .. } => Err(AppError::UnsupportedInGui { command: "verify" }),
+        Command::Diff { .. } => Err(AppError::UnsupportedInGui { command: "diff" }),
+        Command::Merge { .. } => Err(AppError::UnsupportedInGui { command: "merge" }),
+        Command::Edit { .. } => Err(AppError::UnsupportedInGui { command: "remove/move/replace" }),
+        Command::Uninstall { .. } => Err(AppError::UnsupportedInGui { command: "uninstall" }),
+        Command::Restore { .. } => Err(AppError::UnsupportedInGui { command: "restore" }),
     }
 }
 
 fn run_extract(
     input_file: &std::path::PathBuf,
     output_dir: Option<&std::path::PathBuf>,
-    overwrite_mode: crate::ui::OverwriteMode,
-) -> Result<(), String> {
-    if !input_file.exists() {
-        return Err(format!("指定されたファイルが存在しません: {}", input_file.display()));
-    }
+    overwrite_modes: OverwriteModes,
+    options: &ExtractOptions,
+) -> std::result::Result<(), AppError> {
+    let reader = PackageReader::open(input_file)?;
 
     let output_dir = if let Some(dir) = output_dir {
         dir.clone()
     } else {
-        let dir_str = pick_output_dir(input_file)?;
+        let dir_str = pick_output_dir(input_file)?.ok_or(AppError::OutputDirNotSelected)?;
         std::path::PathBuf::from(dir_str)
     };
 
-    let tmp_output_dir = output_dir.join(TMP_OUTPUT_DIR);
-    if tmp_output_dir.exists() {
-        std::fs::remove_dir_all(&tmp_output_dir)
-            .map_err(|e| format!("一時ディレクトリの削除に失敗しました: {}", e))?;
-    }
-
     let cancelled = Arc::new(std::sync::atomic::AtomicBool::new(false));
     let mut progress = ProgressWindow::new("処理中...", Arc::clone(&cancelled));
-    let (mut ui_handler, rx) = GuiProgressHandler::new(Arc::clone(&cancelled), overwrite_mode);
-
-    let objects = Arc::new(Mutex::new(HashMap::new()));
-    let objects_clone = Arc::clone(&objects);
+    let (mut ui_handler, rx) = GuiProgressHandler::new(Arc::clone(&cancelled), overwrite_modes);
 
     // ワーカーの結果を共有するための変数
-    let worker_result: Arc<Mutex<Option<Result<(), String>>>> = Arc::new(Mutex::new(None));
+    let worker_result: Arc<Mutex<Option<Result<()>>>> = Arc::new(Mutex::new(None));
     let worker_result_clone = Arc::clone(&worker_result);
 
-    let input_file = input_file.to_path_buf();
-    let tmp_output_dir_clone = tmp_output_dir.clone();
+    // 既存のアセットとGUIDが衝突する場合は、書き込みを始める前にダイアログで続行するか確認する
+    let confirms_guid_conflicts = !options.fail_on_guid_conflict;
+    let options = options.clone();
     let output_dir_clone = output_dir.clone();
 
     // 処理スレッド起動
     let worker_handle = std::thread::spawn(move || {
-        let result = (|| -> Result<(), String> {
-            let mut objects = objects_clone.lock().unwrap();
-
-            // extractionを実行
-            extract_objects(&input_file, &tmp_output_dir_clone, &mut *objects, &mut ui_handler)?;
-
-            // キャンセルチェック
-            if ui_handler.is_cancelled() {
-                return Err("キャンセルされました".to_string());
-            }
-
-            // rebuildを実行
-            rebuild_objects(&objects, &output_dir_clone, &tmp_output_dir_clone, &mut ui_handler)?;
-
-            Ok(())
-        })();
+        // 展開を実行（一時ディレクトリの削除はライブラリ側で行われる）
+        let result = reader
+            .extract(&output_dir_clone, &options, &mut ui_handler)
+            .map(|_| ());
 
         // 結果を共有メモリに保存
         *worker_result_clone.lock().unwrap() = Some(result);
@@ -95,9 +87,13 @@ fn run_extract(
             println!("解凍が完了しました。");
             (true, false)
         }
+        Some(Err(Error::GuidConflict { .. })) if confirms_guid_conflicts => {
+            println!("GUIDの衝突のため展開を中止しました。");
+            return Ok(());
+        }
         Some(Err(e)) => {
             // キャンセルとエラーを区別
-            let is_cancelled = e.contains("キャンセルされました");
+            let is_cancelled = matches!(e, Error::Cancelled);
 
             if is_cancelled {
                 println!("処理がキャンセルされました。");
@@ -117,13 +113,6 @@ fn run_extract(
         }
     };
 
-    // クリーンアップ（常に実行）
-    if tmp_output_dir.exists() {
-        if let Err(e) = std::fs::remove_dir_all(&tmp_output_dir) {
-            eprintln!("警告: 一時ディレクトリの削除に失敗しました: {}", e);
-        }
-    }
-
     // 成功時またはキャンセル時にディレクトリを開く
     if success || was_cancelled {
         open_directory(&output_dir)?;
@@ -132,7 +121,7 @@ fn run_extract(
     Ok(())
 }
 
-fn open_directory(path: &Path) -> Result<(), String> {
+fn open_directory(path: &Path) -> Result<()> {
     let open_result = match std::env::consts::OS {
         "windows" => std::process::Command::new("explorer").arg(&path).status(),
         "macos" => std::process::Command::new("open").arg(&path).status(),
@@ -0,0 +1,46 @@
+//! Unityの.unitypackageファイルを展開・生成するライブラリ
+//!
+//! CLI版・GUI版の実行ファイルはこのクレートを利用している。
+//! ビルドツールなどから直接組み込む場合は `PackageReader` と `PackageWriter` を使用する。
+//!
+//! ```no_run
+//! use unitypackage_extractor::{ExtractOptions, OverwriteMode, PackageReader, SilentHandler};
+//!
+//! let reader = PackageReader::open("input.unitypackage")?;
+//! for entry in reader.entries()? {
+//!     println!("{} {:?}", entry.guid, entry.pathname);
+//! }
+//!
+//! let mut ui_handler = SilentHandler::new(OverwriteMode::Skip);
+//! reader.extract("./MyProject", &ExtractOptions::default(), &mut ui_handler)?;
+//! # Ok::<(), unitypackage_extractor::Error>(())
+//! ```
+
+mod core;
+mod ui;
+
+pub use crate::core::backup::{backup_session_dir, restore_backup};
+pub use crate::core::compress::{compress_directory, CompressOptions};
+pub use crate::core::diff::{render_diff, DiffEntry, DiffFormat, DiffKind};
+pub use crate::core::edit::{edit_package, EditSummary, PackageEdit};
+pub use crate::core::entry::{EntryKind, PackageEntry};
+pub use crate::core::error::{Error, IoOperation, Result, UnsafePathEntry};
+pub use crate::core::extract::ExtractOptions;
+pub use crate::core::filter::PathFilter;
+pub use crate::core::guid::{generate_guid, is_valid_guid};
+pub use crate::core::guid_index::{GuidConflict, GuidConflictKind, GuidIndex};
+pub use crate::core::list::ListEntry;
+pub use crate::core::manifest::{render_manifest, ManifestFormat};
+pub use crate::core::merge::{merge_packages, MergeConflict, MergeConflictKind, MergePolicy};
+pub use crate::core::plan::{PlannedAction, PlannedOperation};
+pub use crate::core::policy::{OverwritePolicy, OverwriteRule};
+pub use crate::core::reader::{ExtractPlan, ExtractPreflight, ExtractSummary, PackageReader};
+pub use crate::core::uninstall::UninstallSummary;
+pub use crate::core::upgrade::UpgradeMove;
+pub use crate::core::verify::{VerifyIssue, VerifyIssueKind};
+pub use crate::core::writer::PackageWriter;
+pub use crate::ui::{OverwriteAction, OverwriteMode, OverwriteModes, OverwriteTarget, SilentHandler, UiHandler};
+#[cfg(not(feature = "gui"))]
+pub use crate::ui::cli::CliProgressHandler;
+#[cfg(feature = "gui")]
+pub use crate::ui::gui::{pick_output_dir, GuiProgressHandler, ProgressWindow};
@@ -1,8 +1,7 @@
 #![cfg_attr(all(not(debug_assertions), feature = "gui"), windows_subsystem = "windows")]
 
 mod args;
-mod core;
-mod ui;
+mod error;
 
 #[cfg(feature = "gui")]
 mod gui_main;
@@ -17,7 +16,7 @@ fn main() {
         if let Err(e) = gui_main::run() {
             MessageDialog::new()
                 .set_title("エラー")
-                .set_description(&e)
+                .set_description(e.to_string())
                 .show();
         }
     }
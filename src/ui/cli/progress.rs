@@ -1,25 +1,50 @@
-use crate::ui::{UiHandler, OverwriteMode, OverwriteAction};
+use crate::core::guid_index::GuidConflict;
+use crate::ui::{UiHandler, OverwriteMode, OverwriteModes, OverwriteAction, OverwriteTarget};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 pub struct CliProgressHandler {
-    overwrite_mode: OverwriteMode,
+    overwrite_modes: OverwriteModes,
     cancelled: Arc<AtomicBool>,
     last_progress: f32,
+    /// 標準出力を機械可読な出力に使うため、進捗を表示しない
+    quiet: bool,
 }
 
 impl CliProgressHandler {
-    pub fn new(overwrite_mode: OverwriteMode) -> Self {
+    /// `OverwriteMode` を渡すとmetaとassetに同じモードを使用する
+    pub fn new(overwrite_modes: impl Into<OverwriteModes>) -> Self {
         Self {
-            overwrite_mode,
+            overwrite_modes: overwrite_modes.into(),
             cancelled: Arc::new(AtomicBool::new(false)),
             last_progress: 0.0,
+            quiet: false,
+        }
+    }
+
+    /// 進捗を表示せず、上書き時のメッセージのみ標準エラー出力に書くハンドラーを作成
+    pub fn quiet(overwrite_modes: impl Into<OverwriteModes>) -> Self {
+        Self {
+            quiet: true,
+            ..Self::new(overwrite_modes)
+        }
+    }
+
+    fn report(&self, message: String) {
+        if self.quiet {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
         }
     }
 }
 
 impl UiHandler for CliProgressHandler {
     fn update_progress(&mut self, value: f32, text: &str) {
+        if self.quiet {
+            return;
+        }
+
         // 進捗率が1%以上変わった場合のみ表示
         if (value - self.last_progress) >= 0.01 || value >= 1.0 {
             println!("[{:>3.0}%] {}", value * 100.0, text);
@@ -28,21 +53,24 @@ impl UiHandler for CliProgressHandler {
     }
 
     fn finish(&mut self) {
+        if self.quiet {
+            return;
+        }
         println!("[100%] 完了");
     }
 
-    fn confirm_overwrite(&mut self, path: &str) -> OverwriteAction {
-        match self.overwrite_mode {
+    fn confirm_overwrite(&mut self, path: &str, target: OverwriteTarget) -> OverwriteAction {
+        match self.overwrite_modes.for_target(target) {
             OverwriteMode::Overwrite => {
-                println!("上書き: {}", path);
+                self.report(format!("上書き: {}", path));
                 OverwriteAction::Overwrite
             }
             OverwriteMode::Skip => {
-                println!("スキップ: {}", path);
+                self.report(format!("スキップ: {}", path));
                 OverwriteAction::Skip
             }
             OverwriteMode::Rename => {
-                println!("リネーム: {}", path);
+                self.report(format!("リネーム: {}", path));
                 OverwriteAction::Rename
             }
             OverwriteMode::Ask => {
@@ -56,4 +84,13 @@ impl UiHandler for CliProgressHandler {
     fn is_cancelled(&self) -> bool {
         self.cancelled.load(Ordering::SeqCst)
     }
+
+    /// CLI版では確認せず、警告を標準エラー出力に表示して続行する
+    fn confirm_guid_conflicts(&mut self, conflicts: &[GuidConflict]) -> bool {
+        for conflict in conflicts {
+            eprintln!("警告: GUIDが衝突しています: {}", conflict);
+        }
+        eprintln!("警告: Unityが一方のGUIDを振り直すため参照が壊れる可能性があります（--fail-on-guid-conflictで中止できます）");
+        true
+    }
 }
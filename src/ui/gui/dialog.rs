@@ -1,23 +1,39 @@
-use rfd::{FileDialog, MessageDialog};
+use crate::core::error::{Error, IoOperation, Result};
+use crate::core::guid_index::GuidConflict;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
 use std::env;
 use std::path::Path;
 
-// 出力先ディレクトリ選択ダイアログ
-pub fn pick_output_dir(archive_path: &Path) -> Result<String, String> {
-    let abs_path = env::current_dir().map_err(|e| format!("カレントディレクトリ取得失敗: {}", e))?.join(archive_path);
+// 出力先ディレクトリ選択ダイアログ（選択されなかった場合はNone）
+pub fn pick_output_dir(archive_path: &Path) -> Result<Option<String>> {
+    let abs_path = env::current_dir().map_err(Error::io(IoOperation::Metadata, "."))?.join(archive_path);
     let parent_path = abs_path.parent().unwrap_or_else(|| Path::new(""));
     let dir = FileDialog::new()
         .set_title("解凍先フォルダーを選択してください")
         .set_directory(parent_path)
         .pick_folder();
-    match dir {
-        Some(path) => Ok(path.display().to_string()),
-        None => {
-            MessageDialog::new()
-                .set_title("エラー")
-                .set_description("解凍先フォルダーが選択されませんでした。")
-                .show();
-            Err("解凍先フォルダーが選択されませんでした。".to_string())
-        }
+    Ok(dir.map(|path| path.display().to_string()))
+}
+
+/// ダイアログに表示するGUID衝突の最大件数
+const MAX_CONFLICTS_IN_DIALOG: usize = 20;
+
+// GUIDの衝突を表示して展開を続けるか確認するダイアログ
+pub fn confirm_guid_conflicts(conflicts: &[GuidConflict]) -> bool {
+    let mut description = String::from("展開先プロジェクトの既存のアセットとGUIDが衝突しています。\n");
+    for conflict in conflicts.iter().take(MAX_CONFLICTS_IN_DIALOG) {
+        description.push_str(&format!("\n{}", conflict));
     }
+    if conflicts.len() > MAX_CONFLICTS_IN_DIALOG {
+        description.push_str(&format!("\n…他{}件", conflicts.len() - MAX_CONFLICTS_IN_DIALOG));
+    }
+    description.push_str("\n\nUnityが一方のGUIDを振り直すため参照が壊れる可能性があります。展開を続けますか？");
+
+    let result = MessageDialog::new()
+        .set_title("GUIDの衝突")
+        .set_description(description)
+        .set_level(MessageLevel::Warning)
+        .set_buttons(MessageButtons::YesNo)
+        .show();
+    result == MessageDialogResult::Yes
 }
@@ -2,4 +2,4 @@ pub mod progress_window;
 pub mod dialog;
 
 pub use progress_window::{GuiProgressHandler, ProgressWindow};
-pub use dialog::pick_output_dir;
+pub use dialog::{confirm_guid_conflicts, pick_output_dir};
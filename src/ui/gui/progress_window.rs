@@ -1,7 +1,10 @@
 // 進捗状況を表示するウィンドウ（7zip風ダイアログ）
 // クロスプラットフォーム対応: fltk-rs を利用
 
-use crate::ui::{UiHandler, OverwriteAction, OverwriteMode};
+use crate::core::guid_index::GuidConflict;
+use crate::ui::gui::confirm_guid_conflicts;
+use crate::ui::{UiHandler, OverwriteAction, OverwriteMode, OverwriteModes, OverwriteTarget};
+use std::collections::HashMap;
 use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use fltk::{app, button::Button, frame::Frame, prelude::*, window::Window, group::Pack, misc::Progress};
@@ -11,20 +14,25 @@ pub enum ProgressMsg {
     Finish,
     ConfirmOverwrite {
         path: String,
+        target: OverwriteTarget,
         resp_tx: Sender<OverwriteAction>,
     },
+    ConfirmGuidConflicts {
+        conflicts: Vec<GuidConflict>,
+        resp_tx: Sender<bool>,
+    },
 }
 
 pub struct GuiProgressHandler {
     tx: Sender<ProgressMsg>,
     cancelled: Arc<AtomicBool>,
-    overwrite_mode: OverwriteMode,
+    overwrite_modes: OverwriteModes,
 }
 
 impl GuiProgressHandler {
-    pub fn new(cancelled: Arc<AtomicBool>, overwrite_mode: OverwriteMode) -> (Self, Receiver<ProgressMsg>) {
+    pub fn new(cancelled: Arc<AtomicBool>, overwrite_modes: impl Into<OverwriteModes>) -> (Self, Receiver<ProgressMsg>) {
         let (tx, rx) = channel();
-        (Self { tx, cancelled, overwrite_mode }, rx)
+        (Self { tx, cancelled, overwrite_modes: overwrite_modes.into() }, rx)
     }
 }
 
@@ -42,9 +50,10 @@ impl UiHandler for GuiProgressHandler {
         fltk::app::awake();
     }
 
-    fn confirm_overwrite(&mut self, path: &str) -> OverwriteAction {
-        if self.overwrite_mode != OverwriteMode::Ask {
-            return match self.overwrite_mode {
+    fn confirm_overwrite(&mut self, path: &str, target: OverwriteTarget) -> OverwriteAction {
+        let overwrite_mode = self.overwrite_modes.for_target(target);
+        if overwrite_mode != OverwriteMode::Ask {
+            return match overwrite_mode {
                 OverwriteMode::Overwrite => OverwriteAction::Overwrite,
                 OverwriteMode::Skip => OverwriteAction::Skip,
                 OverwriteMode::Rename => OverwriteAction::Rename,
@@ -55,6 +64,7 @@ impl UiHandler for GuiProgressHandler {
         let (resp_tx, resp_rx) = channel();
         let _ = self.tx.send(ProgressMsg::ConfirmOverwrite {
             path: path.to_string(),
+            target,
             resp_tx,
         });
         fltk::app::awake();
@@ -65,6 +75,17 @@ impl UiHandler for GuiProgressHandler {
     fn is_cancelled(&self) -> bool {
         self.cancelled.load(Ordering::SeqCst)
     }
+
+    fn confirm_guid_conflicts(&mut self, conflicts: &[GuidConflict]) -> bool {
+        let (resp_tx, resp_rx) = channel();
+        let _ = self.tx.send(ProgressMsg::ConfirmGuidConflicts {
+            conflicts: conflicts.to_vec(),
+            resp_tx,
+        });
+        fltk::app::awake();
+
+        resp_rx.recv().unwrap_or(false)
+    }
 }
 
 pub struct ProgressWindow {
@@ -75,7 +96,8 @@ pub struct ProgressWindow {
     #[allow(dead_code)]
     cancel_btn: Button,
     cancelled: Arc<AtomicBool>,
-    overwrite_all: Option<OverwriteAction>,
+    /// 「すべて」で選択した応答（metaファイルとassetファイルで別々に保持）
+    overwrite_all: HashMap<OverwriteTarget, OverwriteAction>,
 }
 
 impl ProgressWindow {
@@ -118,7 +140,7 @@ impl ProgressWindow {
             label,
             cancel_btn,
             cancelled,
-            overwrite_all: None,
+            overwrite_all: HashMap::new(),
         }
     }
 
@@ -137,7 +159,6 @@ impl ProgressWindow {
     }
 
     pub fn run_loop(&mut self, rx: Receiver<ProgressMsg>) {
-        let mut auto_rename_all = false; // 追加: 自動リネームを全てに適用するフラグ
         loop {
             match rx.try_recv() {
                 Ok(msg) => {
@@ -148,18 +169,15 @@ impl ProgressWindow {
                         ProgressMsg::Finish => {
                             break;
                         },
-                        ProgressMsg::ConfirmOverwrite { path, resp_tx } => {
-                            // すべて上書き/すべてスキップ/すべて自動リネームが選択済みなら自動応答
-                            if let Some(val) = self.overwrite_all {
-                                let _ = resp_tx.send(val);
-                                continue;
-                            }
-                            if auto_rename_all {
-                                let _ = resp_tx.send(OverwriteAction::Rename);
+                        ProgressMsg::ConfirmOverwrite { path, target, resp_tx } => {
+                            // 同じ種類のファイルですべて上書き/すべてスキップ/すべて自動リネームが選択済みなら自動応答
+                            if let Some(val) = self.overwrite_all.get(&target) {
+                                let _ = resp_tx.send(*val);
                                 continue;
                             }
                             let result = self.show_overwrite_dialog(
                                 path,
+                                target,
                                 &self.cancelled
                             );
                             // キャンセルされた場合は即座にbreak
@@ -171,15 +189,15 @@ impl ProgressWindow {
                                     let _ = resp_tx.send(OverwriteAction::Overwrite);
                                 },
                                 Some(2) => {
-                                    self.overwrite_all = Some(OverwriteAction::Overwrite);
+                                    self.overwrite_all.insert(target, OverwriteAction::Overwrite);
                                     let _ = resp_tx.send(OverwriteAction::Overwrite);
                                 },
                                 Some(3) => {
-                                    self.overwrite_all = Some(OverwriteAction::Skip);
+                                    self.overwrite_all.insert(target, OverwriteAction::Skip);
                                     let _ = resp_tx.send(OverwriteAction::Skip);
                                 },
                                 Some(4) => {
-                                    auto_rename_all = true;
+                                    self.overwrite_all.insert(target, OverwriteAction::Rename);
                                     let _ = resp_tx.send(OverwriteAction::Rename);
                                 },
                                 _ => {
@@ -187,6 +205,9 @@ impl ProgressWindow {
                                 },
                             }
                         },
+                        ProgressMsg::ConfirmGuidConflicts { conflicts, resp_tx } => {
+                            let _ = resp_tx.send(confirm_guid_conflicts(&conflicts));
+                        },
                     }
                 },
                 Err(std::sync::mpsc::TryRecvError::Empty) => {},
@@ -202,16 +223,27 @@ impl ProgressWindow {
         self.close();
     }
 
-    fn show_overwrite_dialog(&self, path: String, cancelled: &Arc<AtomicBool>) -> Option<u8> {
+    fn show_overwrite_dialog(&self, path: String, target: OverwriteTarget, cancelled: &Arc<AtomicBool>) -> Option<u8> {
         use fltk::{window::Window, button::Button, frame::Frame, prelude::*, enums::Align};
         use std::rc::Rc;
         use std::cell::RefCell;
         use std::sync::atomic::Ordering;
 
-        let win = Rc::new(RefCell::new(Window::new(0, 0, 400, 140, "ファイルの上書き確認")));
+        // 「すべて」の選択は同じ種類のファイルにだけ適用する
+        let (window_title, message) = match target {
+            OverwriteTarget::Meta => (
+                "metaファイルの上書き確認",
+                "出力先のフォルダーに同じ名前のmetaファイルが存在しています。\n「すべて」はmetaファイルにのみ適用されます。",
+            ),
+            OverwriteTarget::Asset => (
+                "ファイルの上書き確認",
+                "出力先のフォルダーに既存ファイルへの同じ名前のファイルが存在しています。\n「すべて」はmetaファイル以外にのみ適用されます。",
+            ),
+        };
+        let win = Rc::new(RefCell::new(Window::new(0, 0, 400, 140, window_title)));
 
         // タイトルメッセージ
-        let mut title_frame = Frame::new(20, 10, 360, 30, "出力先のフォルダーに既存ファイルへの同じ名前のファイルが存在しています。");
+        let mut title_frame = Frame::new(20, 10, 360, 30, message);
         title_frame.set_align(Align::Left | Align::Inside);
         title_frame.set_label_size(11);
 
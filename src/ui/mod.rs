@@ -1,3 +1,5 @@
+use crate::core::guid_index::GuidConflict;
+
 /// 上書きモード
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OverwriteMode {
@@ -11,6 +13,41 @@ pub enum OverwriteMode {
     Ask,
 }
 
+/// metaファイルとassetファイルそれぞれの上書きモード
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverwriteModes {
+    /// `.meta` ファイルの上書きモード
+    pub meta: OverwriteMode,
+    /// assetファイルの上書きモード
+    pub asset: OverwriteMode,
+}
+
+impl OverwriteModes {
+    /// 対象の種類に応じた上書きモード
+    pub fn for_target(&self, target: OverwriteTarget) -> OverwriteMode {
+        match target {
+            OverwriteTarget::Meta => self.meta,
+            OverwriteTarget::Asset => self.asset,
+        }
+    }
+}
+
+/// metaファイルとassetファイルに同じモードを使用する
+impl From<OverwriteMode> for OverwriteModes {
+    fn from(mode: OverwriteMode) -> Self {
+        Self { meta: mode, asset: mode }
+    }
+}
+
+/// 上書き確認の対象の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverwriteTarget {
+    /// `.meta` ファイル
+    Meta,
+    /// assetファイル
+    Asset,
+}
+
 /// 上書き確認の応答
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OverwriteAction {
@@ -34,13 +71,24 @@ pub trait UiHandler: Send {
 
     /// ファイル上書き確認
     /// path: 上書き対象のファイルパス
+    /// target: 上書き対象がmetaファイルかassetファイルか
     /// 戻り値: 上書きアクション
-    fn confirm_overwrite(&mut self, path: &str) -> OverwriteAction;
+    fn confirm_overwrite(&mut self, path: &str, target: OverwriteTarget) -> OverwriteAction;
 
     /// キャンセルされたかチェック
     fn is_cancelled(&self) -> bool;
+
+    /// 展開先プロジェクトの既存のアセットとGUIDが衝突する場合に、書き込みを始める前に展開を続けるか確認
+    /// 戻り値: 続行する場合はtrue（既定では続行する）
+    fn confirm_guid_conflicts(&mut self, _conflicts: &[GuidConflict]) -> bool {
+        true
+    }
 }
 
+pub mod silent;
+
+pub use silent::SilentHandler;
+
 // feature flagに応じてモジュールを公開
 #[cfg(feature = "gui")]
 pub mod gui;
@@ -0,0 +1,35 @@
+use crate::ui::{OverwriteAction, OverwriteMode, OverwriteModes, OverwriteTarget, UiHandler};
+
+/// 何も表示しないUIハンドラー
+/// ライブラリとして組み込む場合など、進捗表示が不要なときに使用する
+pub struct SilentHandler {
+    overwrite_modes: OverwriteModes,
+}
+
+impl SilentHandler {
+    /// 上書きモードを指定して作成（`OverwriteMode` を渡すとmetaとassetに同じモードを使用する）
+    /// 確認できないため、`OverwriteMode::Ask` はスキップとして扱う
+    pub fn new(overwrite_modes: impl Into<OverwriteModes>) -> Self {
+        Self {
+            overwrite_modes: overwrite_modes.into(),
+        }
+    }
+}
+
+impl UiHandler for SilentHandler {
+    fn update_progress(&mut self, _value: f32, _text: &str) {}
+
+    fn finish(&mut self) {}
+
+    fn confirm_overwrite(&mut self, _path: &str, target: OverwriteTarget) -> OverwriteAction {
+        match self.overwrite_modes.for_target(target) {
+            OverwriteMode::Overwrite => OverwriteAction::Overwrite,
+            OverwriteMode::Rename => OverwriteAction::Rename,
+            OverwriteMode::Skip | OverwriteMode::Ask => OverwriteAction::Skip,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}